@@ -0,0 +1,92 @@
+use serde::{ Serialize, Deserialize };
+
+/// Asset names the `minimal` preset skips: music, records and every language but English.
+const MINIMAL_EXCLUDE: &[&str] = &[
+    "minecraft/sounds/music/**",
+    "minecraft/sounds/records/**",
+    "minecraft/lang/*",
+    "music/**",
+    "newmusic/**",
+    "records/**",
+    "lang/*",
+];
+
+const MINIMAL_INCLUDE: &[&str] = &[
+    "minecraft/lang/en_us.json",
+    "minecraft/lang/en_us.lang",
+    "lang/en_US.lang",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetPreset {
+    /// Every object in the asset index.
+    #[default]
+    Full,
+    /// Skips music and non-English languages.
+    Minimal,
+}
+
+impl std::str::FromStr for AssetPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(Self::Full),
+            "minimal" => Ok(Self::Minimal),
+            _ => Err(format!("unknown asset preset `{}`, expected `full` or `minimal`", s)),
+        }
+    }
+}
+
+/// Which asset objects an instance downloads, matched against their names in the index.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct AssetFilter {
+    pub preset: AssetPreset,
+    /// Glob patterns of names to skip as well, `*` stopping at `/` and `**` crossing it.
+    pub exclude: Vec<String>,
+    /// Glob patterns of names to keep even when the preset or `exclude` would skip them.
+    pub include: Vec<String>,
+}
+
+impl From<AssetPreset> for AssetFilter {
+    fn from(preset: AssetPreset) -> Self {
+        Self { preset, ..Default::default() }
+    }
+}
+
+impl AssetFilter {
+    pub fn allows(&self, name: &str) -> bool {
+        let (preset_exclude, preset_include) = match self.preset {
+            AssetPreset::Full => (&[][..], &[][..]),
+            AssetPreset::Minimal => (MINIMAL_EXCLUDE, MINIMAL_INCLUDE),
+        };
+
+        let excluded = preset_exclude.iter().copied().chain(self.exclude.iter().map(String::as_str))
+            .any(|pattern| glob(pattern, name));
+
+        !excluded || preset_include.iter().copied().chain(self.include.iter().map(String::as_str))
+            .any(|pattern| glob(pattern, name))
+    }
+}
+
+/// Matches `name` against `pattern`, where `?` is any one character, `*` any run of
+/// characters within a path segment and `**` any run at all.
+pub(crate) fn glob(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[u8], name: &[u8]) -> bool {
+        match pattern {
+            [] => name.is_empty(),
+            // `a/**/b` also matches `a/b`
+            [b'*', b'*', rest @ ..] => rest.strip_prefix(b"/").is_some_and(|rest| matches(rest, name))
+                || (0..=name.len()).any(|i| matches(rest, &name[i..])),
+            [b'*', rest @ ..] => (0..=name.len())
+                .take_while(|&i| i == 0 || name[i - 1] != b'/')
+                .any(|i| matches(rest, &name[i..])),
+            [b'?', rest @ ..] => name.first().is_some_and(|&c| c != b'/') && matches(rest, &name[1..]),
+            [c, rest @ ..] => name.first() == Some(c) && matches(rest, &name[1..]),
+        }
+    }
+
+    matches(pattern.as_bytes(), name.as_bytes())
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{ BufReader, BufWriter };
+use std::path::{ Path, PathBuf };
+use zip::{ ZipArchive, ZipWriter };
+use zip::write::FileOptions;
+use nizziel::{ Download, Hash };
+use crate::{ paths, InstanceTrait };
+use crate::download::DownloadHelper;
+use crate::error::BundleError;
+use crate::verify::{ self, HashIndex };
+
+/// Zips the instance directory into `output`, paths relative to `base_dir`.
+///
+/// With `store` set the archive also carries the cached meta and every library, native
+/// and asset the instance resolves to, so it can be imported and launched offline.
+pub(crate) fn export(instance: &dyn InstanceTrait, base_dir: &Path, store: bool, output: &Path) -> Result<(), BundleError> {
+    let mut files = paths::files(DownloadHelper::paths(instance).get("instance")?)?;
+
+    if store {
+        files.extend(instance.metadata()?);
+
+        let dls = instance.resolve()?;
+        let index = HashIndex::open(DownloadHelper::paths(instance).get("hashes")?);
+        let present = verify::downloaded(&dls.downloads, &index);
+        index.save()?;
+
+        let missing = dls.downloads.iter().zip(present)
+            .filter(|(dl, present)| !dl.unzip && !present)
+            .map(|(dl, _)| dl.path.clone())
+            .collect::<Vec<PathBuf>>();
+
+        if !missing.is_empty() {
+            return Err(BundleError::Incomplete(missing));
+        }
+
+        files.extend(dls.downloads.into_iter().filter(|dl| !dl.unzip).map(|dl| dl.path));
+        files.extend(instance.asset_copies()?.into_iter().map(|(_, copy)| copy).filter(|copy| copy.exists()));
+    }
+
+    files.sort();
+    files.dedup();
+
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(output)?));
+    for file in &files {
+        let name = file.strip_prefix(base_dir)
+            .map_err(|_| BundleError::OutsideBaseDir(file.clone()))?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        zip.start_file(name, FileOptions::default())?;
+        std::io::copy(&mut BufReader::new(File::open(file)?), &mut zip)?;
+    }
+    zip.finish()?;
+
+    log::info!("Exported {} files to {}", files.len(), output.display());
+    Ok(())
+}
+
+/// An archive written by [`export`], opened for import.
+pub(crate) struct Bundle {
+    zip: ZipArchive<BufReader<File>>,
+    pub(crate) instance: String,
+}
+
+/// Where an archive entry belongs.
+#[derive(PartialEq, Eq)]
+enum Kind {
+    Instance,
+    Meta,
+    Store,
+}
+
+impl Bundle {
+    /// Opens `archive`, refusing it unless every entry belongs to its one instance, the meta
+    /// cache, or the libraries and assets stores.
+    pub(crate) fn open(archive: &Path, base_dir: &Path) -> Result<Self, BundleError> {
+        let mut zip = ZipArchive::new(BufReader::new(File::open(archive)?))?;
+
+        let mut instances = zip.file_names()
+            .filter_map(|name| name.strip_prefix("instances/")?.strip_suffix("/state.json"))
+            .filter(|name| !name.contains('/'))
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        let instance = match instances.len() {
+            1 => instances.remove(0),
+            _ => return Err(BundleError::NoInstance(archive.to_path_buf())),
+        };
+
+        if base_dir.join("instances").join(&instance).exists() {
+            return Err(BundleError::InstanceExists(instance));
+        }
+
+        for i in 0..zip.len() {
+            let entry = zip.by_index(i)?;
+            if !entry.is_dir() && entry.enclosed_name().and_then(|name| kind(name, &instance)).is_none() {
+                return Err(BundleError::UnexpectedEntry(entry.name().to_string()));
+            }
+        }
+
+        Ok(Self { zip, instance })
+    }
+
+    /// Whether the archive carries libraries or assets besides the instance and its meta.
+    pub(crate) fn has_store(&self) -> bool {
+        let instance = &self.instance;
+        self.zip.file_names().any(|name| kind(Path::new(name), instance) == Some(Kind::Store))
+    }
+
+    /// Unpacks the instance directory and whatever meta isn't cached yet, which is enough
+    /// for the instance to be opened.
+    pub(crate) fn unpack_instance(&mut self, base_dir: &Path) -> Result<(), BundleError> {
+        for i in 0..self.zip.len() {
+            let mut entry = self.zip.by_index(i)?;
+            let Some(name) = entry.enclosed_name().map(Path::to_path_buf) else { continue };
+            let path = base_dir.join(&name);
+
+            match kind(&name, &self.instance) {
+                Some(Kind::Instance) => {},
+                Some(Kind::Meta) if !path.exists() => {},
+                _ => continue,
+            }
+
+            if entry.is_dir() {
+                std::fs::create_dir_all(&path)?;
+                continue;
+            }
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::io::copy(&mut entry, &mut BufWriter::new(File::create(&path)?))?;
+        }
+
+        Ok(())
+    }
+
+    /// Unpacks the libraries and assets `instance` needs that the stores don't hold yet,
+    /// each checked against the hash its meta expects. Files already in the stores are
+    /// left as they are, and files the instance doesn't need are left out.
+    pub(crate) fn unpack_store(&mut self, base_dir: &Path, instance: &dyn InstanceTrait) -> Result<(), BundleError> {
+        // the asset index and published checksums are what everything else is resolved from
+        let metadata = instance.metadata()?.into_iter().map(|path| (path, None)).collect::<HashMap<PathBuf, Option<Download>>>();
+        let (written, mut rejected) = self.unpack_store_files(base_dir, &metadata)?;
+
+        let mut expected = metadata;
+        for download in instance.resolve()?.downloads.into_iter().filter(|dl| !dl.unzip) {
+            expected.insert(download.path.clone(), Some(download));
+        }
+        for (object, copy) in instance.asset_copies()? {
+            let hash = object.file_name().map(|name| Hash::Sha1(name.to_string_lossy().into_owned()));
+            expected.insert(copy.clone(), Some(Download { path: copy, hash, ..Default::default() }));
+        }
+
+        // now that they're known, hold the metadata unpacked first to the same check
+        for path in written {
+            if let Some(Some(download)) = expected.get(&path) {
+                if !matches(download, &path)? {
+                    std::fs::remove_file(&path)?;
+                    rejected.push(path);
+                }
+            }
+        }
+
+        for name in self.zip.file_names().filter(|name| kind(Path::new(name), &self.instance) == Some(Kind::Store)) {
+            if !expected.contains_key(&base_dir.join(name)) {
+                log::debug!("Leaving out `{}`, the instance does not use it", name);
+            }
+        }
+
+        let (_, mismatched) = self.unpack_store_files(base_dir, &expected)?;
+        rejected.extend(mismatched);
+
+        match rejected.is_empty() {
+            true => Ok(()),
+            false => Err(BundleError::Mismatched(rejected)),
+        }
+    }
+
+    /// Unpacks the store entries among `expected` not on disk yet, returning those
+    /// written and those refused for not matching their download's hash or size.
+    fn unpack_store_files(&mut self, base_dir: &Path, expected: &HashMap<PathBuf, Option<Download>>) -> Result<(Vec<PathBuf>, Vec<PathBuf>), BundleError> {
+        let (mut written, mut rejected) = (Vec::new(), Vec::new());
+
+        for i in 0..self.zip.len() {
+            let mut entry = self.zip.by_index(i)?;
+            let Some(name) = entry.enclosed_name().map(Path::to_path_buf) else { continue };
+            if entry.is_dir() || kind(&name, &self.instance) != Some(Kind::Store) {
+                continue;
+            }
+
+            let path = base_dir.join(&name);
+            let Some(download) = expected.get(&path) else { continue };
+            if path.exists() {
+                continue;
+            }
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let part = part_path(&path);
+            std::io::copy(&mut entry, &mut BufWriter::new(File::create(&part)?))?;
+
+            if download.as_ref().map_or(Ok(true), |download| matches(download, &part))? {
+                std::fs::rename(&part, &path)?;
+                written.push(path);
+            } else {
+                log::warn!("Leaving out `{}`, it does not match the hash the instance expects", entry.name());
+                std::fs::remove_file(&part)?;
+                rejected.push(path);
+            }
+        }
+
+        Ok((written, rejected))
+    }
+}
+
+/// Where the entry `name` belongs, if anywhere an import may write.
+fn kind(name: &Path, instance: &str) -> Option<Kind> {
+    let mut components = name.components().map(|c| c.as_os_str().to_str());
+    let kind = match (components.next()??, components.next()??) {
+        ("instances", name) if name == instance => Kind::Instance,
+        ("meta", _) => Kind::Meta,
+        ("libraries" | "assets", _) => Kind::Store,
+        _ => return None,
+    };
+
+    Some(kind)
+}
+
+/// Whether the file at `path` has the size and hash `download` expects of it.
+fn matches(download: &Download, path: &Path) -> std::io::Result<bool> {
+    let len = path.metadata()?.len();
+    if download.size.is_some_and(|size| size != len) {
+        return Ok(false)
+    }
+
+    match &download.hash {
+        Some(hash) => Ok(hash.matches(&hash.digest_file(path)?)),
+        None => Ok(true),
+    }
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
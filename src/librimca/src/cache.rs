@@ -0,0 +1,138 @@
+use serde::{ Serialize, Deserialize };
+use reqwest::header::{ ETAG, LAST_MODIFIED, IF_NONE_MATCH, IF_MODIFIED_SINCE };
+use reqwest::StatusCode;
+use std::path::{ Path, PathBuf };
+use std::time::{ Duration, SystemTime, UNIX_EPOCH };
+use crate::error::ApiError;
+use crate::mirror::{ self, Mirror };
+
+/// Stores remote metadata under `base_dir/meta` alongside the validators needed to revalidate it.
+pub struct MetaCache {
+    dir: PathBuf,
+    ttl: Duration,
+    mirrors: Vec<Mirror>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    fetched: u64,
+}
+
+impl MetaCache {
+    pub fn new(dir: &Path, ttl: Duration, mirrors: &[Mirror]) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            ttl,
+            mirrors: mirrors.to_vec(),
+        }
+    }
+
+    pub fn mirrors(&self) -> &[Mirror] {
+        &self.mirrors
+    }
+
+    /// Returns the body of `url`, cached as `name` relative to the meta directory.
+    ///
+    /// A copy younger than the ttl is returned as is, older copies are revalidated with a
+    /// conditional request, and a stale copy is used when the network can't be reached.
+    pub fn get(&self, url: &str, name: &str) -> Result<Vec<u8>, ApiError> {
+        let path = self.dir.join(name);
+        let validators_path = validators_path(&path);
+
+        let cached = std::fs::read(&path).ok();
+        let mut validators: Validators = std::fs::read(&validators_path).ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        if let Some(bytes) = &cached {
+            if now().saturating_sub(validators.fetched) < self.ttl.as_secs() {
+                return Ok(bytes.clone())
+            }
+        }
+
+        let client = reqwest::blocking::Client::new();
+        let mut result = None;
+
+        for candidate in mirror::candidates(&self.mirrors, url) {
+            let mut request = client.get(&candidate);
+            if cached.is_some() {
+                if let Some(etag) = &validators.etag {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+
+                if let Some(last_modified) = &validators.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+
+            let response = request.send().and_then(|r| r.error_for_status());
+            let done = response.is_ok();
+            result = Some(response);
+            if done {
+                break;
+            }
+        }
+
+        let response = match result.expect("candidates always include the upstream url") {
+            Ok(response) => response,
+            Err(err) => return match cached {
+                Some(bytes) => {
+                    log::warn!("Could not refresh `{}`, using cached copy: {}", url, err);
+                    Ok(bytes)
+                },
+                None => Err(err.into())
+            }
+        };
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(bytes) = cached {
+                validators.fetched = now();
+                write_validators(&validators_path, &validators)?;
+                return Ok(bytes)
+            }
+        }
+
+        let header = |name| response.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from);
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            fetched: now(),
+        };
+
+        let bytes = response.bytes()?.to_vec();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, &bytes)?;
+        write_validators(&validators_path, &validators)?;
+
+        Ok(bytes)
+    }
+}
+
+/// Extension of the validators kept beside each cached response.
+const VALIDATORS_EXTENSION: &str = "cache";
+
+/// Where the validators of the response cached at `path` are kept.
+pub(crate) fn validators_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(VALIDATORS_EXTENSION);
+    path.with_file_name(name)
+}
+
+/// Whether `path` holds the validators of a cached response rather than a response.
+pub(crate) fn is_validators(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == VALIDATORS_EXTENSION)
+}
+
+fn write_validators(path: &Path, validators: &Validators) -> Result<(), ApiError> {
+    std::fs::write(path, serde_json::to_vec(validators)?)?;
+    Ok(())
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
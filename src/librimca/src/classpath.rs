@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+use crate::maven::MavenCoordinate;
+
+/// Separates classpath entries for the JVM on this platform.
+pub const SEPARATOR: char = if cfg!(target_os = "windows") { ';' } else { ':' };
+
+/// Classpath entries keyed by Maven `group:artifact[:classifier]`, so a later component
+/// shipping another version of a library replaces the earlier one instead of joining it.
+#[derive(Default)]
+pub struct Classpath {
+    entries: Vec<(String, PathBuf)>,
+}
+
+impl Classpath {
+    /// Adds the jar of the library named `name` (`group:artifact:version[:classifier]`).
+    pub fn push(&mut self, name: &str, path: PathBuf) {
+        let key = name.parse::<MavenCoordinate>()
+            .map(|coordinate| coordinate.key())
+            .unwrap_or_else(|_| name.to_string());
+
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some(entry) => {
+                log::debug!("Classpath entry `{}` overridden by {}", key, path.display());
+                entry.1 = path;
+            },
+            None => self.entries.push((key, path)),
+        }
+    }
+
+    /// Joins the entries with the platform separator, warning about jars that aren't there.
+    pub fn build(&self) -> String {
+        let mut classpath = String::new();
+
+        for (key, path) in &self.entries {
+            if !path.is_file() {
+                log::warn!("Classpath entry `{}` is missing: {}", key, path.display());
+            }
+
+            if !classpath.is_empty() {
+                classpath.push(SEPARATOR);
+            }
+            classpath.push_str(&path.to_string_lossy());
+        }
+
+        classpath
+    }
+}
@@ -0,0 +1,173 @@
+use std::path::{ Path, PathBuf };
+use std::time::SystemTime;
+
+/// Text found in crash output and the advice it calls for, in the order hints are given.
+const HINTS: &[(&[&str], &str)] = &[
+    (
+        &["UnsupportedClassVersionError", "compiled by a more recent version of the Java Runtime"],
+        "The game or a mod needs a newer Java than the instance uses; point the `java` component in state.json at a newer runtime.",
+    ),
+    (
+        &["UnsatisfiedLinkError", "Failed to locate library"],
+        "Native libraries could not be loaded; run `rimca verify --repair` on the instance to extract them again.",
+    ),
+    (
+        &["java.lang.OutOfMemoryError", "insufficient memory for the Java Runtime", "Could not reserve enough space"],
+        "The JVM ran out of memory; raise `-Xmx` in the `java` component's arguments, or lower it if the heap can't be reserved at all.",
+    ),
+    (
+        &["of fabricloader", "Incompatible mod set", "Incompatible mods found", "requires version", "which is missing!"],
+        "A mod needs a different loader or game version; update the mod, or download the instance again with a matching loader.",
+    ),
+];
+
+/// What the crash files left behind by a launch point at.
+#[derive(Debug, Default)]
+pub struct Diagnosis {
+    pub reports: Vec<CrashReport>,
+    pub hints: Vec<&'static str>,
+}
+
+impl Diagnosis {
+    pub fn is_empty(&self) -> bool {
+        self.reports.is_empty() && self.hints.is_empty()
+    }
+}
+
+/// A crash report written by the game, or an `hs_err_pid*.log` written by the JVM.
+#[derive(Debug)]
+pub struct CrashReport {
+    pub path: PathBuf,
+    /// The exception, or for the JVM the signal and problematic frame.
+    pub headline: Option<String>,
+    pub suspected_mods: Vec<String>,
+}
+
+/// Reads the crash files in `instance_dir` written since `since`, matching them and
+/// the launch's own `log` against the known causes.
+pub(crate) fn scan(instance_dir: &Path, since: SystemTime, log: Option<&Path>) -> std::io::Result<Diagnosis> {
+    let mut diagnosis = Diagnosis::default();
+    let mut texts = Vec::new();
+
+    for path in crash_files(instance_dir)? {
+        if path.metadata()?.modified()? < since {
+            continue;
+        }
+
+        let text = String::from_utf8_lossy(&std::fs::read(&path)?).into_owned();
+        let is_jvm = path.parent() == Some(instance_dir);
+        diagnosis.reports.push(CrashReport {
+            headline: if is_jvm { jvm_headline(&text) } else { headline(&text) },
+            suspected_mods: suspected_mods(&text),
+            path,
+        });
+        texts.push(text);
+    }
+
+    if let Some(log) = log.filter(|log| log.is_file()) {
+        texts.push(String::from_utf8_lossy(&std::fs::read(log)?).into_owned());
+    }
+
+    diagnosis.hints = HINTS.iter()
+        .filter(|(patterns, _)| texts.iter().any(|text| patterns.iter().any(|pattern| text.contains(pattern))))
+        .map(|(_, hint)| *hint)
+        .collect();
+
+    Ok(diagnosis)
+}
+
+/// Every `crash-reports/*.txt` and `hs_err_pid*.log` in the instance.
+pub(crate) fn crash_files(instance_dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = files_named(&instance_dir.join("crash-reports"), |name| name.ends_with(".txt"))?;
+    files.extend(files_named(instance_dir, |name| name.starts_with("hs_err_pid") && name.ends_with(".log"))?);
+    Ok(files)
+}
+
+fn files_named(dir: &Path, matches: impl Fn(&str) -> bool) -> std::io::Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new())
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.file_name().and_then(|n| n.to_str()).is_some_and(&matches) {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// The first line after the report's `Description:`, which is the exception thrown.
+fn headline(text: &str) -> Option<String> {
+    text.lines()
+        .skip_while(|line| !line.starts_with("Description:"))
+        .skip(1)
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(String::from)
+}
+
+/// The signal or exception the JVM died of, with the native frame it happened in.
+///
+/// ```text
+/// #  SIGSEGV (0xb) at pc=0x00007f3c1c2b5a40, pid=4242, tid=4243
+/// ...
+/// # Problematic frame:
+/// # C  [liblwjgl.so+0x1a40]
+/// ```
+fn jvm_headline(text: &str) -> Option<String> {
+    let lines = text.lines().map(|line| line.trim_start_matches('#').trim()).collect::<Vec<&str>>();
+
+    let cause = lines.iter()
+        .find(|line| line.contains(" at pc=") || line.starts_with("There is insufficient memory"))
+        .copied();
+    let frame = lines.iter()
+        .position(|line| *line == "Problematic frame:")
+        .and_then(|i| lines.get(i + 1))
+        .copied();
+
+    match (cause, frame) {
+        (Some(cause), Some(frame)) => Some(format!("{} in {}", cause, frame)),
+        (cause, frame) => cause.or(frame).map(String::from),
+    }
+}
+
+/// Mods the report blames, from a Forge `Suspected Mod(s):` section or, on Fabric,
+/// the mixin configs named in the stack trace.
+fn suspected_mods(text: &str) -> Vec<String> {
+    let mut mods: Vec<String> = Vec::new();
+    let mut push = |name: String| if !name.is_empty() && !mods.contains(&name) { mods.push(name) };
+
+    let mut lines = text.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some(rest) = line.trim().strip_prefix("Suspected Mod") else { continue };
+        let rest = rest.trim_start_matches('s').trim_start_matches(':').trim();
+
+        if rest.eq_ignore_ascii_case("NONE") {
+            continue;
+        }
+        if !rest.is_empty() {
+            push(rest.to_string());
+        }
+        // `\tExample Mod (examplemod), Version: 1.0`, then `\t\tIssue tracker URL: ...`
+        while let Some(line) = lines.next_if(|line| line.starts_with('\t')) {
+            if !line.starts_with("\t\t") {
+                push(line.trim().split(", Version:").next().unwrap_or_default().to_string());
+            }
+        }
+    }
+
+    // `at net.minecraft.class_310.handler$zbc000$onInit(class_310.java) [sodium.mixins.json]`
+    for line in text.lines().map(str::trim).filter(|line| line.starts_with("at ")).take(64) {
+        for part in line.split(|c: char| c.is_whitespace() || "[]{}:,".contains(c)) {
+            if let Some(config) = part.strip_suffix(".mixins.json") {
+                push(config.to_string());
+            }
+        }
+    }
+
+    mods
+}
@@ -1,14 +1,17 @@
-use std::fs;
-use std::path::Path;
+use std::path::PathBuf;
 use crate::error::DownloadError;
-use nizziel::{ download, Downloads };
+use nizziel::{ download, Downloads, Progress };
+use std::sync::mpsc::Sender;
 use crate::state::State;
 use crate::paths::Paths;
-use crate::Instance;
+use crate::{ Instance, Settings };
+use crate::mirror;
+use crate::verify::{ self, HashIndex };
 
 pub trait DownloadHelper {
     fn state(&self) -> &State;
     fn paths(&self) -> &Paths;
+    fn settings(&self) -> &Settings;
 }
 
 impl <T> DownloadHelper for Instance<T> {
@@ -19,56 +22,148 @@ impl <T> DownloadHelper for Instance<T> {
     fn paths(&self) -> &Paths {
         &self.paths
     }
+
+    fn settings(&self) -> &Settings {
+        &self.settings
+    }
 }
 
 pub trait DownloadSequence: DownloadHelper {
-    fn collect_urls(&mut self) -> Result<Downloads, DownloadError>;
+    /// Every file the instance needs, whether or not it is already on disk.
+    fn resolve(&self) -> Result<Downloads, DownloadError>;
+    /// The cached metadata files the instance is resolved from.
+    fn metadata(&self) -> Result<Vec<PathBuf>, DownloadError>;
     fn create_state(&mut self) -> Result<(), DownloadError>;
+    /// Asset objects that have to be placed under their names too, as `(object, copy)` pairs.
+    fn asset_copies(&self) -> Result<Vec<(PathBuf, PathBuf)>, DownloadError>;
+    /// Unpacks the downloaded native jars into the natives directory.
+    fn extract_natives(&self) -> Result<(), DownloadError>;
+    /// Fetches the checksums [`Self::resolve`] pins downloads to that the metadata leaves
+    /// to the repositories, the only part of resolving that goes to the network.
+    fn pin_checksums(&self) -> Result<(), DownloadError> {
+        Ok(())
+    }
+
+    /// Links or copies the objects from [`Self::asset_copies`] into place, leaving copies
+    /// of the right size alone.
+    fn materialise_assets(&self) -> Result<(), DownloadError> {
+        for (object, copy) in self.asset_copies()? {
+            let size = std::fs::metadata(&object)?.len();
+            if std::fs::metadata(&copy).is_ok_and(|m| m.len() == size) {
+                continue;
+            }
+
+            if let Some(parent) = copy.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let _ = std::fs::remove_file(&copy);
+
+            // a hard link costs no space, a copy works across filesystems
+            if std::fs::hard_link(&object, &copy).is_err() {
+                std::fs::copy(&object, &copy)?;
+            }
+        }
 
-    fn download(&mut self) -> Result<(), DownloadError> {
+        Ok(())
+    }
+
+    /// The part of [`Self::resolve`] that is missing or doesn't match its hash.
+    fn collect_urls(&mut self) -> Result<Downloads, DownloadError> {
+        let mut dls = self.resolve()?;
+        let index = HashIndex::open(self.paths().get("hashes")?);
+        let mut present = verify::downloaded(&dls.downloads, &index).into_iter();
+        index.save()?;
+
+        // archives are extracted rather than kept, so there is nothing to compare against
+        dls.downloads.retain(|dl| !present.next().unwrap_or(false) || dl.unzip);
+        Ok(dls)
+    }
+
+    fn download(&mut self, progress: Option<Sender<Progress>>) -> Result<(), DownloadError> {
         self.create_state()?;
         self.state().write(self.paths().get("instance")?)?;
 
+        self.pin_checksums()?;
+        let urls = self.collect_urls()?;
+        self.fetch(urls, progress.clone())?;
+
+        // the asset objects only resolve once the index fetched just now is on disk
         let urls = self.collect_urls()?;
-        self.spawn_thread(urls)
+        if !urls.downloads.is_empty() {
+            self.fetch(urls, progress)?;
+        }
+        self.extract_natives()?;
+        self.materialise_assets()
+    }
+
+    /// Re-hashes every file the instance resolves to; with `repair` set the broken ones are
+    /// fetched again and the natives and named assets put back. The report describes the files as found.
+    fn verify(&mut self, repair: bool, progress: Option<Sender<Progress>>) -> Result<verify::Report, DownloadError> {
+        // bit rot and copies keeping the modification time leave the index none the wiser
+        let index = HashIndex::fresh(self.paths().get("hashes")?);
+        let (report, broken) = verify::check(self.resolve()?, &index);
+        index.save()?;
+        log::info!("Checked {} files, {} missing, {} corrupt", report.checked, report.missing.len(), report.corrupt.len());
+        if let Some(vulnerability) = &report.log4j {
+            log::warn!("log4j-core {} is vulnerable to Log4Shell, it will be mitigated at launch", vulnerability.version);
+        }
+
+        if repair {
+            if !broken.downloads.is_empty() {
+                self.fetch(broken, progress.clone())?;
+
+                // a repaired asset index lists objects that couldn't be checked before it was
+                let unlisted = self.collect_urls()?;
+                if !unlisted.downloads.is_empty() {
+                    self.fetch(unlisted, progress)?;
+                }
+            }
+            self.extract_natives()?;
+            self.materialise_assets()?;
+        }
+
+        Ok(report)
+    }
+
+    /// Downloads `urls` under the configured policy and mirrors.
+    fn fetch(&mut self, mut urls: Downloads, progress: Option<Sender<Progress>>) -> Result<(), DownloadError> {
+        urls.policy = self.settings().policy.clone();
+        mirror::apply(&self.settings().mirrors, &mut urls);
+        urls.progress = progress;
+
+        // nizziel checks every hash as it writes, so what it fetched needn't be hashed again
+        let fetched = urls.downloads.iter()
+            .filter(|dl| !dl.unzip)
+            .filter_map(|dl| Some((dl.path.clone(), dl.hash.clone()?)))
+            .collect::<Vec<_>>();
+
+        self.spawn_thread(urls)?;
+
+        let index = HashIndex::open(self.paths().get("hashes")?);
+        for (path, hash) in &fetched {
+            index.record(path, hash);
+        }
+        index.save()?;
+        Ok(())
     }
 
     fn spawn_thread(&mut self, dls: Downloads) -> Result<(), DownloadError> {
         log::info!("Downloading!");
         let before = std::time::Instant::now();
         let rt = tokio::runtime::Builder::new_multi_thread()
-            .worker_threads(10)
+            .worker_threads(dls.policy.worker_threads.max(1))
             .enable_io()
             .enable_time()
             .build()?;
 
-        rt.block_on(
-            async move {
-                download(dls).await.unwrap();
-            }
-        );
+        let report = rt.block_on(download(dls))?;
+        log::info!("Downloaded {} files ({} bytes), {} already present", report.succeeded.len(), report.bytes, report.skipped.len());
         log::info!("Time taken: {:.2?}", before.elapsed());
 
-        // 下载完成后清理文件natives
-        let natives_dir = &self.paths().get("natives")?;
-
-        if Path::exists(natives_dir) {
-            for entry in fs::read_dir(natives_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-
-                // 检查是否是文件，如果是，则检查扩展名。
-                if path.is_file() {
-                    if path.extension().map_or(true, |ext| ext != "dll") {
-                        // 如果扩展名不是 dll，则删除文件。
-                        fs::remove_file(path)?;
-                    }
-                } else if path.is_dir() {
-                    // 如果是文件夹，直接删除。
-                    fs::remove_dir_all(path)?;
-                }
-            }
+        if !report.is_complete() {
+            return Err(DownloadError::Incomplete(report.failed));
         }
+
         Ok(())
     }
-}
\ No newline at end of file
+}
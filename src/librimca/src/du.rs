@@ -0,0 +1,85 @@
+use std::collections::{ BTreeMap, HashMap, HashSet };
+use std::path::{ Path, PathBuf };
+use crate::{ paths, InstanceTrait };
+use crate::download::DownloadHelper;
+use crate::error::DownloadError;
+use crate::gc::STORES;
+
+/// Disk usage of the installed instances and the shared stores.
+#[derive(Debug, Default)]
+pub struct Usage {
+    pub instances: Vec<InstanceUsage>,
+    /// Total size of each shared store, by directory name.
+    pub stores: Vec<(String, u64)>,
+}
+
+#[derive(Debug, Default)]
+pub struct InstanceUsage {
+    pub name: String,
+    /// The instance directory itself.
+    pub own: u64,
+    /// Store files no other instance uses.
+    pub exclusive: u64,
+    /// Store files other instances use as well.
+    pub shared: u64,
+}
+
+/// Measures every instance against the files it resolves to, the same set a download
+/// would fetch, so store files are split into what each instance alone holds on to.
+pub(crate) fn measure(base_dir: &Path, instances: &BTreeMap<String, Box<dyn InstanceTrait>>) -> Result<Usage, DownloadError> {
+    let mut sizes: HashMap<PathBuf, u64> = HashMap::new();
+    let mut users: HashMap<PathBuf, usize> = HashMap::new();
+    let mut resolved = Vec::with_capacity(instances.len());
+
+    for (name, instance) in instances {
+        let dir = DownloadHelper::paths(instance.as_ref()).get("instance")?;
+
+        // files resolved into the instance directory are already counted as its own
+        let files = instance.metadata()?.into_iter()
+            .chain(instance.resolve()?.downloads.into_iter().map(|dl| dl.path))
+            .chain(instance.asset_copies()?.into_iter().map(|(_, copy)| copy))
+            .filter(|path| !path.starts_with(dir))
+            .collect::<HashSet<PathBuf>>();
+
+        for file in &files {
+            if let Ok(metadata) = std::fs::metadata(file) {
+                sizes.insert(file.clone(), metadata.len());
+                *users.entry(file.clone()).or_default() += 1;
+            }
+        }
+
+        let own = paths::files(dir)?.iter()
+            .filter_map(|file| file.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum();
+
+        resolved.push((name.clone(), own, files));
+    }
+
+    let mut usage = Usage::default();
+    for (name, own, files) in resolved {
+        let mut instance = InstanceUsage { name, own, ..Default::default() };
+
+        for file in &files {
+            let Some(size) = sizes.get(file) else { continue };
+            if users.get(file).is_some_and(|count| *count > 1) {
+                instance.shared += size;
+            } else {
+                instance.exclusive += size;
+            }
+        }
+
+        usage.instances.push(instance);
+    }
+
+    for store in STORES {
+        let bytes = paths::files(&base_dir.join(store))?.iter()
+            .filter_map(|file| file.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum();
+
+        usage.stores.push((store.to_string(), bytes));
+    }
+
+    Ok(usage)
+}
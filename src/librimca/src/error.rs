@@ -18,12 +18,16 @@ pub enum Error {
     PathError(#[from] PathError),
     #[error("account error: {0}")]
     AccountError(#[from] AccountError),
+    #[error("bundle error: {0}")]
+    BundleError(#[from] BundleError),
 }
 
 #[derive(Error, Debug)]
 pub enum LaunchError {
     #[error("`{0}` arguments were not found")]
     ArgumentsNotFound(LaunchArguments),
+    #[error("no cached meta for `{0}` version `{1}`, download the instance while online first")]
+    MetaNotFound(String, String),
     #[error("io error: {0}")]
     IoError(#[from] std::io::Error),
     // #[error("io")]
@@ -40,6 +44,12 @@ pub enum LaunchError {
     PathError(#[from] PathError),
     #[error("serde_json error: {0}")]
     SerdeJsonError(#[from] serde_json::Error),
+    #[error("maven error: {0}")]
+    MavenError(#[from] MavenError),
+    #[error("could not extract natives, run `rimca verify --repair` on the instance: {0}")]
+    NativesError(#[source] DownloadError),
+    #[error("the logging config `{}` has to be patched against Log4Shell but was never downloaded, run `rimca verify --repair` on the instance", .0.display())]
+    LoggingConfigNotFound(std::path::PathBuf),
 }
 
 #[derive(Error, Debug)]
@@ -113,6 +123,8 @@ pub enum DownloadError {
     GameVersionNotFound(String),
     #[error("no game version was specified")]
     VersionNotSpecified,
+    #[error("{} file(s) could not be downloaded:{}", .0.len(), .0.iter().map(|f| format!("\n  {}: {}", f.download.path.display(), f.reason)).collect::<String>())]
+    Incomplete(Vec<nizziel::Failure>),
 
     #[error("IO")]
     IoError(#[from] std::io::Error),
@@ -133,6 +145,16 @@ pub enum DownloadError {
     NizzielError(#[from] nizziel::Error),
     #[error("path error: {0}")]
     PathError(#[from] PathError),
+    #[error("zip error: {0}")]
+    ZipError(#[from] zip::result::ZipError),
+    #[error("maven error: {0}")]
+    MavenError(#[from] MavenError),
+}
+
+#[derive(Error, Debug)]
+pub enum MavenError {
+    #[error("`{0}` is not a maven coordinate, expected `group:artifact:version[:classifier][@extension]`")]
+    InvalidCoordinate(String),
 }
 
 #[derive(Error, Debug)]
@@ -149,4 +171,30 @@ pub enum ApiError {
     NizzielError(#[from] nizziel::Error),
     #[error("reqwest error: {0}")]
     ReqwestError(#[from] reqwest::Error),
+    #[error("io error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum BundleError {
+    #[error("`{0}` does not hold exactly one instance")]
+    NoInstance(std::path::PathBuf),
+    #[error("instance: `{0}` already exists")]
+    InstanceExists(String),
+    #[error("{} file(s) are missing or corrupt, download the instance again first:{}", .0.len(), .0.iter().map(|p| format!("\n  {}", p.display())).collect::<String>())]
+    Incomplete(Vec<std::path::PathBuf>),
+    #[error("`{0}` is outside the base directory")]
+    OutsideBaseDir(std::path::PathBuf),
+    #[error("`{0}` belongs neither to the instance nor to the libraries, assets or meta stores")]
+    UnexpectedEntry(String),
+    #[error("{} file(s) in the bundle do not match the hashes the instance expects and were left out, run `rimca verify --repair` to download them:{}", .0.len(), .0.iter().map(|p| format!("\n  {}", p.display())).collect::<String>())]
+    Mismatched(Vec<std::path::PathBuf>),
+    #[error("io error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("zip error: {0}")]
+    ZipError(#[from] zip::result::ZipError),
+    #[error("download error: {0}")]
+    DownloadError(#[from] DownloadError),
+    #[error("path error: {0}")]
+    PathError(#[from] PathError),
 }
\ No newline at end of file
@@ -1,16 +1,20 @@
 use crate::error::ApiError;
+use crate::cache::MetaCache;
 
 const BASE_URL: &str = "https://meta.fabricmc.net"; //concatp!()
 pub(crate) const MANIFEST: &str = "https://meta.fabricmc.net/v2/versions/loader";
 pub(crate) const META: &str = "https://meta.fabricmc.net/v2/versions/loader/{game_version}/{loader_version}/profile/json";
 
-pub fn best_version(ver_id: &str) -> Result<String, ApiError> {
+pub fn best_version(cache: &MetaCache, ver_id: &str) -> Result<String, ApiError> {
     let url = format!("{}/{}", MANIFEST, ver_id);
-    let fjson = reqwest::blocking::get(&url)?.json::<serde_json::Value>().unwrap();
+    let bytes = cache.get(&url, &format!("net.fabricmc/loader/{}.json", ver_id))?;
+    let fjson = serde_json::from_slice::<serde_json::Value>(&bytes)?;
 
     let loader = fjson
         .as_array().ok_or_else(|| ApiError::LoaderDoesNotExistForGameVer(ver_id.to_string()))?
         .first().ok_or_else(|| ApiError::LoaderDoesNotExistForGameVer(ver_id.to_string()))?;
         
-    Ok(loader["loader"]["version"].as_str().unwrap().to_string())
+    loader["loader"]["version"].as_str()
+        .map(String::from)
+        .ok_or_else(|| ApiError::LoaderDoesNotExistForGameVer(ver_id.to_string()))
 }
\ No newline at end of file
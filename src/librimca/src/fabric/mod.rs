@@ -1,18 +1,22 @@
 pub mod api;
 
-mod models;
+pub(crate) mod models;
 use models::Meta;
 
 use crate::Instance;
 use crate::download::DownloadSequence;
 use crate::vanilla::Vanilla;
 use crate::launch::LaunchSequence;
-use crate::error::{ LaunchError, DownloadError };
+use crate::error::{ LaunchError, DownloadError, PathError };
 use crate::state::Component;
 use crate::Paths;
+use crate::cache::MetaCache;
+use crate::mirror;
+use crate::maven::{ MavenCoordinate, Resolver };
 
 use std::io::BufReader;
-use nizziel::{ Download, Downloads };
+use std::path::PathBuf;
+use nizziel::Downloads;
 
 pub struct Fabric {
     pub version: String,
@@ -21,22 +25,22 @@ pub struct Fabric {
 }
 
 impl Fabric {
-    pub fn new(paths: &Paths, vanilla: Instance<Vanilla>) -> Result<Self, DownloadError> {
-        let version = api::best_version(&vanilla.inner.version.id)?;
-
-        let meta = {
-            let path = paths.get("meta")?.join("net.fabricmc").join(format!("{}.json", &vanilla.inner.version.id));
-            if let Ok(file) = std::fs::File::open(path) {
-                let reader = BufReader::new(file); 
-                serde_json::from_reader(reader)?               
-            } else {
-                let meta_str = nizziel::blocking::download(
-                    &api::META
-                        .replace("{game_version}", &vanilla.inner.version.id)
-                        .replace("{loader_version}", &version), 
-                    &paths.get("meta")?.join("net.fabricmc").join(format!("{}.json", &version)), false)?;
-                serde_json::from_slice(&meta_str)?
-            }
+    /// Picks the best loader for the vanilla version, fetching its profile if it isn't cached yet.
+    pub fn new(paths: &Paths, cache: &MetaCache, vanilla: Instance<Vanilla>) -> Result<Self, DownloadError> {
+        let version = api::best_version(cache, &vanilla.inner.meta.id)?;
+
+        let path = meta_path(paths, &version, &vanilla.inner.meta.id)?;
+        let meta = if let Ok(file) = std::fs::File::open(&path) {
+            let reader = BufReader::new(file);
+            serde_json::from_reader(reader)?
+        } else {
+            let meta_str = mirror::download(
+                cache.mirrors(),
+                &api::META
+                    .replace("{game_version}", &vanilla.inner.meta.id)
+                    .replace("{loader_version}", &version),
+                &path)?;
+            serde_json::from_slice(&meta_str)?
         };
 
         Ok(Self {
@@ -45,31 +49,76 @@ impl Fabric {
             vanilla
         })
     }
+
+    /// Loads the loader profile from the local meta cache only, never touching the network.
+    pub fn read(paths: &Paths, version: &str, vanilla: Instance<Vanilla>) -> Result<Self, LaunchError> {
+        let path = meta_path(paths, version, &vanilla.inner.meta.id)?;
+        // older downloads cached the profile under the loader version alone
+        let legacy = paths.get("meta")?.join("net.fabricmc").join(format!("{}.json", version));
+
+        let meta: Meta = [path, legacy].iter()
+            .filter_map(|path| std::fs::File::open(path).ok())
+            .filter_map(|file| serde_json::from_reader::<_, Meta>(BufReader::new(file)).ok())
+            .find(|meta| meta.inherits_from.eq(&vanilla.inner.meta.id))
+            .ok_or_else(|| LaunchError::MetaNotFound(String::from("net.fabricmc"), version.to_string()))?;
+
+        Ok(Self {
+            version: version.to_string(),
+            meta,
+            vanilla
+        })
+    }
+}
+
+fn meta_path(paths: &Paths, version: &str, game_version: &str) -> Result<PathBuf, PathError> {
+    Ok(paths.get("meta")?.join("net.fabricmc").join(format!("fabric-loader-{}-{}.json", version, game_version)))
 }
 
 impl DownloadSequence for Instance<Fabric> {
-    fn collect_urls(&mut self) -> Result<Downloads, DownloadError> {
-        let mut dls = self.inner.vanilla.collect_urls()?;
+    fn resolve(&self) -> Result<Downloads, DownloadError> {
+        let mut dls = self.inner.vanilla.resolve()?;
 
+        let resolver = Resolver::new(self.paths.get("libraries")?, &self.settings.mirrors);
         for lib in &self.inner.meta.libraries {
-            let split = lib.name.split(':').collect::<Vec<&str>>();
-            let local_path = format!("{}/{}/{}/{}-{}.jar", 
-                split[0].to_string().replace('.', "/"), split[1], split[2], split[1], split[2]
-            );
-
-            let path = self.paths.get("libraries")?.join(&local_path);
-            if !path.exists() {
-                dls.downloads.push(Download {
-                    url: format!("{}{}", lib.url, local_path),
-                    path,
-                    unzip: false
-                });
-            }
+            dls.downloads.push(resolver.resolve(&lib.name.parse()?, Some(&lib.url)));
         }
 
         Ok(dls)
     }
 
+    fn metadata(&self) -> Result<Vec<PathBuf>, DownloadError> {
+        let mut files = self.inner.vanilla.metadata()?;
+        let path = meta_path(&self.paths, &self.inner.version, &self.inner.meta.inherits_from)?;
+        let legacy = self.paths.get("meta")?.join("net.fabricmc").join(format!("{}.json", self.inner.version));
+
+        files.push(if !path.exists() && legacy.exists() { legacy } else { path });
+
+        // published checksums the libraries were pinned to
+        let resolver = Resolver::new(self.paths.get("libraries")?, &self.settings.mirrors);
+        for lib in &self.inner.meta.libraries {
+            files.extend(resolver.sidecars(&lib.name.parse()?));
+        }
+
+        Ok(files)
+    }
+
+    fn asset_copies(&self) -> Result<Vec<(PathBuf, PathBuf)>, DownloadError> {
+        self.inner.vanilla.asset_copies()
+    }
+
+    fn pin_checksums(&self) -> Result<(), DownloadError> {
+        let resolver = Resolver::new(self.paths.get("libraries")?, &self.settings.mirrors);
+        for lib in &self.inner.meta.libraries {
+            resolver.pin(&lib.name.parse()?, Some(&lib.url));
+        }
+
+        Ok(())
+    }
+
+    fn extract_natives(&self) -> Result<(), DownloadError> {
+        self.inner.vanilla.extract_natives()
+    }
+
     fn create_state(&mut self) -> Result<(), DownloadError> {
         self.inner.vanilla.create_state()?;
         self.state = self.inner.vanilla.state.clone();
@@ -94,24 +143,16 @@ impl LaunchSequence for Instance<Fabric> {
     }
 
     fn get_classpath(&self) -> Result<String, LaunchError> { 
-        let mut classpath = self.inner.vanilla.get_classpath()?;
+        // loader libraries come last, so they win over the vanilla copies of asm and friends
+        let mut classpath = self.inner.vanilla.classpath()?;
 
-        let dir_name = self.paths.get("libraries")?;
-        classpath.push(';');
+        let libraries = self.paths.get("libraries")?;
         for lib in &self.inner.meta.libraries {
-            let split = lib.name.split(':').collect::<Vec<&str>>();
-            let path = format!("/{}/{}/{}/{}-{}.jar", 
-                split[0].to_string().replace('.', "/"), split[1], split[2], split[1], split[2]
-            );
-
-            classpath.push_str(dir_name.to_str().unwrap());
-            classpath.push_str(&path);
-            classpath.push(';');
+            let coordinate: MavenCoordinate = lib.name.parse()?;
+            classpath.push(&lib.name, libraries.join(coordinate.path()));
         }
 
-        classpath.pop();
-
-        Ok(classpath)
+        Ok(classpath.build())
     }
     
     fn get_jvm_arguments(&self, classpath: &str) -> Result<Vec<String>, LaunchError> { 
@@ -0,0 +1,191 @@
+use std::io::Write;
+use std::sync::OnceLock;
+use serde::{ Serialize, Deserialize };
+use regex::Regex;
+use chrono::{ Local, TimeZone };
+
+/// log4j levels, least severe first.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl std::str::FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" => Ok(Self::Trace),
+            "debug" => Ok(Self::Debug),
+            "info" => Ok(Self::Info),
+            "warn" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            "fatal" => Ok(Self::Fatal),
+            _ => Err(format!("unknown log level `{}`", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Self::Trace => "TRACE",
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warn => "WARN",
+            Self::Error => "ERROR",
+            Self::Fatal => "FATAL",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One event the game logged through the XML layout of its log4j configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Milliseconds since the epoch.
+    pub timestamp: Option<i64>,
+    pub level: Level,
+    pub logger: String,
+    pub thread: String,
+    pub message: String,
+    pub throwable: Option<String>,
+}
+
+impl Record {
+    /// `[12:00:00] [Render thread/INFO] (Minecraft) message`, followed by the stack trace if any.
+    pub fn format(&self) -> String {
+        let time = self.timestamp
+            .and_then(|millis| Local.timestamp_millis_opt(millis).single())
+            .map(|time| time.format("%H:%M:%S").to_string())
+            .unwrap_or_default();
+        let logger = self.logger.rsplit('.').next().unwrap_or_default();
+
+        let mut line = format!("[{}] [{}/{}] ({}) {}\n", time, self.thread, self.level, logger, self.message);
+        if let Some(throwable) = &self.throwable {
+            line.push_str(throwable.trim_end());
+            line.push('\n');
+        }
+        line
+    }
+}
+
+/// A line of game output once events are pieced back together.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Line {
+    Record(Record),
+    Text(String),
+}
+
+/// Pieces `<log4j:Event>` elements back together from the lines of the game's output,
+/// passing anything else through as it is.
+#[derive(Default)]
+pub(crate) struct Parser {
+    event: Option<String>,
+}
+
+impl Parser {
+    /// Feeds one line, including its newline, getting back whatever it completes.
+    pub(crate) fn push(&mut self, line: &str) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        if line.trim_start().starts_with("<log4j:Event") {
+            // a new event before the last one closed means the last wasn't one after all
+            lines.extend(self.event.take().map(Line::Text));
+            self.event = Some(String::new());
+        }
+
+        match &mut self.event {
+            Some(event) => {
+                event.push_str(line);
+                if line.contains("</log4j:Event>") {
+                    let event = self.event.take().unwrap_or_default();
+                    lines.push(parse(&event).map_or(Line::Text(event), Line::Record));
+                }
+            },
+            None => lines.push(Line::Text(line.to_string())),
+        }
+
+        lines
+    }
+
+    /// Whatever was left unfinished when the output ended.
+    pub(crate) fn finish(&mut self) -> Option<Line> {
+        self.event.take().map(Line::Text)
+    }
+}
+
+fn parse(event: &str) -> Option<Record> {
+    static ATTRIBUTES: OnceLock<Regex> = OnceLock::new();
+    let attributes = ATTRIBUTES.get_or_init(|| Regex::new(r#"(\w+)="([^"]*)""#).unwrap());
+
+    let tag = &event[..event.find('>')?];
+    let attribute = |name: &str| attributes.captures_iter(tag)
+        .find(|c| &c[1] == name)
+        .map(|c| unescape(&c[2]));
+
+    Some(Record {
+        timestamp: attribute("timestamp").and_then(|t| t.parse().ok()),
+        level: attribute("level")?.parse().ok()?,
+        logger: attribute("logger").unwrap_or_default(),
+        thread: attribute("thread").unwrap_or_default(),
+        message: element(event, "log4j:Message").unwrap_or_default(),
+        throwable: element(event, "log4j:Throwable"),
+    })
+}
+
+/// The text of `<name>`, whether written as CDATA or escaped.
+fn element(event: &str, name: &str) -> Option<String> {
+    let start = event.find(&format!("<{}>", name))? + name.len() + 2;
+    let end = start + event[start..].find(&format!("</{}>", name))?;
+    let text = &event[start..end];
+
+    Some(match text.strip_prefix("<![CDATA[").and_then(|t| t.strip_suffix("]]>")) {
+        // `]]>` in a message splits it into several sections
+        Some(cdata) => cdata.replace("]]]]><![CDATA[>", "]]>"),
+        None => unescape(text),
+    })
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Where game output is echoed besides the log, and how much of it.
+pub(crate) struct Echo {
+    pub(crate) out: Box<dyn Write + Send>,
+    pub(crate) level: Level,
+    pub(crate) colour: bool,
+}
+
+impl Echo {
+    pub(crate) fn write(&mut self, line: &Line) -> std::io::Result<()> {
+        match line {
+            Line::Record(record) if record.level < self.level => return Ok(()),
+            Line::Record(record) if self.colour => {
+                let colour = match record.level {
+                    Level::Trace | Level::Debug => "\x1b[2m",
+                    Level::Info => "",
+                    Level::Warn => "\x1b[33m",
+                    Level::Error | Level::Fatal => "\x1b[31m",
+                };
+                writeln!(self.out, "{}{}\x1b[0m", colour, record.format().trim_end())?;
+            },
+            Line::Record(record) => self.out.write_all(record.format().as_bytes())?,
+            Line::Text(text) => self.out.write_all(text.as_bytes())?,
+        }
+
+        self.out.flush()
+    }
+}
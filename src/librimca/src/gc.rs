@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+use std::path::{ Path, PathBuf };
+use crate::{ cache, paths, InstanceTrait };
+use crate::error::DownloadError;
+
+/// Shared stores below the base directory that instances draw from.
+pub(crate) const STORES: &[&str] = &["libraries", "assets", "meta"];
+
+/// Files in the shared stores that no instance references.
+#[derive(Debug, Default)]
+pub struct GcReport {
+    pub files: Vec<PathBuf>,
+    pub bytes: u64,
+}
+
+/// Finds the files in the shared stores none of `instances` resolve to and, unless
+/// `dry_run` is set, removes them along with any directories left empty.
+///
+/// Metadata kept by the meta cache itself, such as the version manifest, is shared by
+/// every download rather than owned by an instance and is left in place.
+pub(crate) fn collect(base_dir: &Path, instances: &[Box<dyn InstanceTrait>], dry_run: bool) -> Result<GcReport, DownloadError> {
+    let mut referenced = HashSet::new();
+    for instance in instances {
+        referenced.extend(instance.metadata()?);
+        referenced.extend(instance.resolve()?.downloads.into_iter().map(|dl| dl.path));
+        referenced.extend(instance.asset_copies()?.into_iter().map(|(_, copy)| copy));
+    }
+
+    let mut report = GcReport::default();
+    for store in STORES {
+        for file in paths::files(&base_dir.join(store))? {
+            if referenced.contains(&file) || is_cache_entry(&file) {
+                continue;
+            }
+
+            report.bytes += file.metadata()?.len();
+            report.files.push(file);
+        }
+    }
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    for file in &report.files {
+        std::fs::remove_file(file)?;
+    }
+
+    for store in STORES {
+        remove_empty_dirs(&base_dir.join(store))?;
+    }
+
+    log::info!("Removed {} files, {} bytes", report.files.len(), report.bytes);
+    Ok(report)
+}
+
+/// Whether `file` is a meta cache entry or its `.cache` validators.
+fn is_cache_entry(file: &Path) -> bool {
+    cache::is_validators(file) || cache::validators_path(file).exists()
+}
+
+fn remove_empty_dirs(dir: &Path) -> std::io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(())
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            remove_empty_dirs(&path)?;
+            if std::fs::read_dir(&path)?.next().is_none() {
+                std::fs::remove_dir(&path)?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,11 +1,16 @@
-use crate::{ Instance, Paths };
+use crate::{ Instance, Paths, Settings };
 use crate::error::{ LaunchError, StateError };
 use crate::state::{ State, Component };
-use std::process::{ Command, Stdio };
+use crate::supervisor::{ self, LaunchMode, Launched };
+use crate::classpath::SEPARATOR;
+use crate::log4j;
+use std::path::Path;
+use std::process::Command;
 
 pub trait LaunchHelper {
     fn state(&self) -> &State;
     fn paths(&self) -> &Paths;
+    fn settings(&self) -> &Settings;
     fn output(&self) -> bool;
 }
 
@@ -18,27 +23,33 @@ impl <T> LaunchHelper for Instance<T> {
         &self.paths
     } 
 
+    fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
     fn output(&self) -> bool {
         self.output
     }
 }
 
 pub trait LaunchSequence: LaunchHelper {
-    fn launch(&self, username: &str) -> Result<(), LaunchError> {
+    fn launch(&self, username: &str, mode: LaunchMode) -> Result<Launched, LaunchError> {
         let game_opts = self.get_game_options(username)?;
         log::debug!("Game Options: {:?}", game_opts);
 
         let classpath = self.get_classpath()?;
         log::debug!("Classpath: {}", classpath);
 
-        let jvm_args = self.get_jvm_arguments(&classpath)?;
+        let mut jvm_args = self.get_jvm_arguments(&classpath)?;
+        if let Some(vulnerability) = log4j::scan(classpath.split(SEPARATOR).map(Path::new)) {
+            log4j::mitigate(&vulnerability, &mut jvm_args, self.paths().get("instance")?)?;
+        }
         log::debug!("Jvm Arguments: {:?}", jvm_args);
 
         let main_class = self.get_main_class()?;
         log::debug!("Main Class: {}", main_class);
 
-        self.execute(jvm_args, &main_class, game_opts)?;
-        Ok(())
+        self.execute(jvm_args, &main_class, game_opts, mode)
     }
 
     fn get_main_class(&self) -> Result<String, LaunchError>;
@@ -46,7 +57,7 @@ pub trait LaunchSequence: LaunchHelper {
     fn get_classpath(&self) -> Result<String, LaunchError>;
     fn get_jvm_arguments(&self, classpath: &str) -> Result<Vec<String>, LaunchError>;
 
-    fn execute(&self, jvm_args: Vec<String>, main_class: &str, game_opts: Vec<String>) -> Result<(), LaunchError> {
+    fn execute(&self, jvm_args: Vec<String>, main_class: &str, game_opts: Vec<String>, mode: LaunchMode) -> Result<Launched, LaunchError> {
         if let Ok(Component::JavaComponent { path, .. }) = self.state().get_component("java") {
             let (exe, args) = match &self.state().wrapper {
                 Some(wrapper) => (wrapper.as_str(), &["java"][..]),
@@ -62,13 +73,10 @@ pub trait LaunchSequence: LaunchHelper {
 
             if self.output() {
                 log::info!("JVM output disabled");
-                command.stdout(Stdio::null()).stderr(Stdio::null());
             }
 
-            log::debug!("Spawning command: {:?}", command);
-            command.spawn()?;
-
-            return Ok(())
+            let echo = (!self.output()).then_some(self.settings().game_log_level);
+            return supervisor::run(command, self.paths().get("instance")?, mode, echo)
         }
 
         Err(LaunchError::StateError(StateError::ComponentNotFound(String::from("java"))))
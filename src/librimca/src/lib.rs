@@ -2,14 +2,31 @@
 // #![feature(provide_any)]
 
 mod state;
-use state::{ State, Component };
+use state::State;
 
 mod download;
 pub use download::DownloadSequence;
+pub use nizziel::{ Progress, DownloadPolicy };
 
 mod launch;
 pub use launch::LaunchSequence;
 
+mod supervisor;
+pub use supervisor::{ LaunchMode, Launched };
+
+mod diagnose;
+pub use diagnose::{ Diagnosis, CrashReport };
+
+mod gamelog;
+pub use gamelog::{ Level, Record };
+
+pub mod log4j;
+
+mod classpath;
+
+pub mod maven;
+pub use maven::MavenCoordinate;
+
 pub mod vanilla;
 pub use vanilla::Vanilla;
 
@@ -17,9 +34,33 @@ pub mod fabric;
 pub use fabric::Fabric;
 
 mod error;
-pub use error::{ Error, StateError };
+pub use error::{ Error, MavenError, StateError };
 
 mod verify;
+pub use verify::Report as VerifyReport;
+
+mod bundle;
+
+mod gc;
+pub use gc::GcReport;
+
+mod du;
+pub use du::{ Usage, InstanceUsage };
+
+mod cache;
+use cache::MetaCache;
+
+mod settings;
+pub use settings::Settings;
+
+mod assets;
+pub use assets::{ AssetFilter, AssetPreset };
+
+pub mod mirror;
+pub use mirror::Mirror;
+
+mod server;
+pub use server::serve;
 
 mod auth;
 use auth::Accounts;
@@ -27,30 +68,64 @@ use auth::Accounts;
 mod paths;
 use paths::Paths;
 
+#[cfg(test)]
+mod test;
+
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::time::Duration;
+use std::sync::mpsc::Sender;
 
 pub struct Instance<T> {
     paths: Paths,
+    settings: Settings,
     output: bool,
     state: State,
     inner: T,
 }
 
 impl<T> Instance<T> {
-    fn get(state: State, paths: Paths, output: bool, version: Option<String>) -> Result<Box<dyn InstanceTrait>, Error> {
+    fn get(state: State, paths: Paths, settings: &Settings, output: bool, version: Option<String>) -> Result<Box<dyn InstanceTrait>, Error> {
+        let cache = MetaCache::new(paths.get("meta")?, Duration::from_secs(settings.meta_ttl), &settings.mirrors);
+        let vanilla = Instance::<Vanilla> { 
+            paths: paths.clone(), 
+            state: state.clone(),
+            output, 
+            settings: settings.clone(),
+            inner: Vanilla::new(&paths, &cache, version)?
+        };
+
+        match state.scenario.as_ref() { 
+            "vanilla" => Ok(Box::new(vanilla)),
+            "fabric" => Ok(Box::new(
+                Instance::<Fabric> {
+                    inner: Fabric::new(&paths, &cache, vanilla)?,  
+                    paths, 
+                    settings: settings.clone(),
+                    output,
+                    state, 
+            })),
+            _ => Err(Error::StateError(StateError::ScenarioDoesNotExist(state.scenario)))
+        }
+    } 
+
+    /// Builds an instance purely from its `state.json` and the local meta cache.
+    fn read(state: State, paths: Paths, settings: &Settings, output: bool) -> Result<Box<dyn InstanceTrait>, Error> {
         let vanilla = Instance::<Vanilla> { 
+            inner: Vanilla::read(&paths, state.get_version("net.minecraft")?)?,
             paths: paths.clone(), 
+            settings: settings.clone(),
             state: state.clone(),
             output, 
-            inner: Vanilla::new(&paths, version)?
         };
 
         match state.scenario.as_ref() { 
             "vanilla" => Ok(Box::new(vanilla)),
             "fabric" => Ok(Box::new(
                 Instance::<Fabric> {
-                    inner: Fabric::new(&paths, vanilla)?,  
+                    inner: Fabric::read(&paths, state.get_version("net.fabricmc")?, vanilla)?,  
                     paths, 
+                    settings: settings.clone(),
                     output,
                     state, 
             })),
@@ -62,26 +137,27 @@ impl<T> Instance<T> {
 pub trait InstanceTrait: LaunchSequence + DownloadSequence {}
 impl<T> InstanceTrait for T where T: LaunchSequence + DownloadSequence {}
 
-pub fn download(instance: &str, version: Option<String>, scenario: Option<String>, base_dir: &Path) -> Result<(), Error> {
-    let mut paths = Paths::default();
-    let instance_path = base_dir.join("instances").join(instance);
-    std::fs::create_dir_all(&instance_path)?;
+/// Downloads `instance`, keeping the version, scenario and asset filter it was installed with
+/// unless they are given. New instances default to the latest vanilla release.
+pub fn download(instance: &str, version: Option<String>, scenario: Option<String>, assets: Option<AssetFilter>, base_dir: &Path, settings: &Settings, progress: Option<Sender<Progress>>) -> Result<(), Error> {
+    let paths = instance_paths(instance, base_dir);
+    std::fs::create_dir_all(paths.get("instance")?)?;
 
-    paths.0.insert("natives".to_string(), instance_path.join("natives")); 
-    paths.0.insert("instance".to_string(), instance_path);
-    paths.0.insert("meta".to_string(), base_dir.join("meta")); 
-    paths.0.insert("assets".to_string(), base_dir.join("assets")); 
-    paths.0.insert("libraries".to_string(), base_dir.join("libraries")); 
+    let previous = State::read(paths.get("instance")?).ok();
+    let version = version.or_else(|| Some(previous.as_ref()?.get_version("net.minecraft").ok()?.to_string()));
+    let scenario = scenario
+        .or_else(|| Some(previous.as_ref()?.scenario.clone()))
+        .unwrap_or_else(|| "vanilla".to_string());
+    let mut state = State::from_scenario(scenario);
+    state.assets = assets.or_else(|| previous.as_ref()?.assets.clone());
+    state.resolution = previous.and_then(|previous| previous.resolution);
 
-    let scenario = scenario.unwrap_or_else(|| "vanilla".to_string());
-    let state = State::from_scenario(scenario);
-
-    Instance::<Box<dyn InstanceTrait>>::get(state, paths, true, version)?.download()?;
+    Instance::<Box<dyn InstanceTrait>>::get(state, paths, settings, true, version)?.download(progress)?;
 
     Ok(())
 }
 
-pub fn launch(instance: &str, username: &str, output: bool, base_dir: &Path) -> Result<(), Error> {
+fn instance_paths(instance: &str, base_dir: &Path) -> Paths {
     let mut paths = Paths::default();
     let instance_path = base_dir.join("instances").join(instance);
 
@@ -92,23 +168,117 @@ pub fn launch(instance: &str, username: &str, output: bool, base_dir: &Path) ->
     paths.0.insert("assets".to_string(), base_dir.join("assets"));
     paths.0.insert("libraries".to_string(), base_dir.join("libraries")); 
     paths.0.insert("accounts".to_string(), base_dir.join("accounts").with_extension("json"));
+    paths.0.insert("hashes".to_string(), base_dir.join("hashes").with_extension("json"));
+    paths
+}
+
+/// Opens an installed instance from its `state.json` and the local meta cache.
+fn open(instance: &str, base_dir: &Path, settings: &Settings, output: bool) -> Result<Box<dyn InstanceTrait>, Error> {
+    let paths = instance_paths(instance, base_dir);
+    if !paths.get("instance")?.exists() {
+        return Err(Error::InstanceDoesNotExist);
+    }
 
     let state = State::read(paths.get("instance")?)?; 
+    Instance::<Box<dyn InstanceTrait>>::read(state, paths, settings, output)
+}
 
-    //maybe move this to state.get_version(); 
-    let version = {
-        if let Component::GameComponent { version, .. } = state.get_component("net.minecraft")? {
-            version.to_string()
-        } else {
-            return Err(Error::StateError(StateError::ComponentNotFound(String::from("net.minecraft"))));
-        }
+pub fn launch(instance: &str, username: &str, output: bool, mode: LaunchMode, base_dir: &Path, settings: &Settings) -> Result<Launched, Error> {
+    Ok(open(instance, base_dir, settings, output)?.launch(username, mode)?)
+}
+
+/// Looks at the crash files written since `instance` was last launched, or the newest
+/// ones if rimca never launched it.
+pub fn diagnose(instance: &str, base_dir: &Path) -> Result<Diagnosis, Error> {
+    let instance_dir = base_dir.join("instances").join(instance);
+    if !instance_dir.is_dir() {
+        return Err(Error::InstanceDoesNotExist)
+    }
+
+    let (log, since) = match supervisor::last_launch(&instance_dir) {
+        Some((log, started)) => (Some(log), started),
+        None => {
+            let newest = diagnose::crash_files(&instance_dir)?.iter()
+                .filter_map(|file| file.metadata().and_then(|m| m.modified()).ok())
+                .max();
+            (None, newest.unwrap_or(std::time::SystemTime::UNIX_EPOCH))
+        },
     };
 
-    Instance::<Box<dyn InstanceTrait>>::get(state, paths, output, Some(version))?.launch(username)?;
+    Ok(diagnose::scan(&instance_dir, since, log.as_deref())?)
+}
+
+/// The PID of the game launched for `instance`, if it's still running.
+pub fn running(instance: &str, base_dir: &Path) -> Option<u32> {
+    supervisor::running(&base_dir.join("instances").join(instance))
+}
+
+/// Checks every file `instance` needs against its expected hash, re-downloading broken ones with `repair`.
+pub fn verify(instance: &str, repair: bool, base_dir: &Path, settings: &Settings, progress: Option<Sender<Progress>>) -> Result<VerifyReport, Error> {
+    Ok(open(instance, base_dir, settings, false)?.verify(repair, progress)?)
+}
+
+/// Opens every installed instance, failing if any of them can't be resolved.
+fn instances(base_dir: &Path, settings: &Settings) -> Result<BTreeMap<String, Box<dyn InstanceTrait>>, Error> {
+    let dir = base_dir.join("instances");
+    let mut instances = BTreeMap::new();
+    if !dir.exists() {
+        return Ok(instances);
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.path().is_dir() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let instance = open(&name, base_dir, settings, false)?;
+        instances.insert(name, instance);
+    }
+
+    Ok(instances)
+}
+
+/// Removes the libraries, assets and meta no remaining instance needs, or with `dry_run`
+/// only reports them. Any instance that can't be resolved aborts the collection.
+pub fn gc(base_dir: &Path, dry_run: bool, settings: &Settings) -> Result<GcReport, Error> {
+    let instances = instances(base_dir, settings)?.into_values().collect::<Vec<_>>();
+    Ok(gc::collect(base_dir, &instances, dry_run)?)
+}
+
+/// Sizes every instance and the shared stores, splitting store files into those an
+/// instance uses alone and those it shares.
+pub fn du(base_dir: &Path, settings: &Settings) -> Result<Usage, Error> {
+    Ok(du::measure(base_dir, &instances(base_dir, settings)?)?)
+}
+
+/// Archives `instance` into `output`; with `bundle` set the archive also carries every
+/// shared file it needs, so it can be imported and launched on a machine without network.
+pub fn export(instance: &str, bundle: bool, output: &Path, base_dir: &Path, settings: &Settings) -> Result<(), Error> {
+    let instance = open(instance, base_dir, settings, false)?;
+    bundle::export(instance.as_ref(), base_dir, bundle, output)?;
 
     Ok(())
 }
 
+/// Unpacks an archive made by [`export`] into `base_dir`, returning the imported instance's name.
+///
+/// Shared files already in the stores are kept, and new ones are only added once they
+/// match the hashes in the instance's meta.
+pub fn import(archive: &Path, base_dir: &Path, settings: &Settings) -> Result<String, Error> {
+    let mut bundle = bundle::Bundle::open(archive, base_dir)?;
+    bundle.unpack_instance(base_dir)?;
+
+    if bundle.has_store() {
+        let instance = open(&bundle.instance, base_dir, settings, false)?;
+        bundle.unpack_store(base_dir, instance.as_ref())?;
+    }
+
+    log::info!("Imported instance `{}` from {}", bundle.instance, archive.display());
+    Ok(bundle.instance)
+}
+
 pub fn delete(instance: &str, base_dir: &Path) -> std::io::Result<()> {
     let instance_path = base_dir.join("instances").join(instance);
     std::fs::remove_dir_all(instance_path)
@@ -0,0 +1,129 @@
+use std::path::{ Path, PathBuf };
+use std::sync::OnceLock;
+use regex::Regex;
+use crate::error::LaunchError;
+
+/// Makes log4j 2.10 and later format messages without evaluating `${...}` lookups.
+pub(crate) const NO_LOOKUPS_FLAG: &str = "-Dlog4j2.formatMsgNoLookups=true";
+
+const CONFIG_ARGUMENT: &str = "-Dlog4j.configurationFile=";
+
+/// Logs to the console without lookups, for versions that don't ship a logging config.
+const FALLBACK_CONFIG: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Configuration status="WARN">
+    <Appenders>
+        <Console name="SysOut" target="SYSTEM_OUT">
+            <PatternLayout pattern="[%d{HH:mm:ss}] [%t/%level]: %msg{nolookups}%n"/>
+        </Console>
+    </Appenders>
+    <Loggers>
+        <Root level="info">
+            <AppenderRef ref="SysOut"/>
+        </Root>
+    </Loggers>
+</Configuration>
+"#;
+
+/// How a vulnerable log4j is kept from evaluating lookups in logged messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mitigation {
+    /// 2.10 and later honour the `log4j2.formatMsgNoLookups` property.
+    NoLookupsFlag,
+    /// Older versions only listen to `%msg{nolookups}` in the logging config itself.
+    PatchedConfig,
+}
+
+/// A log4j-core among an instance's libraries open to Log4Shell (CVE-2021-44228).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vulnerability {
+    pub version: String,
+    pub mitigation: Mitigation,
+}
+
+/// The vulnerable log4j-core among the jars at `paths`, if any.
+pub(crate) fn scan<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<Vulnerability> {
+    paths.into_iter()
+        .filter_map(|path| path.file_name()?.to_str()?.strip_prefix("log4j-core-")?.strip_suffix(".jar"))
+        .find_map(check)
+}
+
+/// Whether log4j-core `version` is affected, from 2.0-beta9 through 2.14.1, and how to mitigate it.
+pub(crate) fn check(version: &str) -> Option<Vulnerability> {
+    let parsed = parse(version)?;
+    if !((2, 0, 0, (1, 9))..=(2, 14, 1, (3, 0))).contains(&parsed) {
+        return None
+    }
+
+    Some(Vulnerability {
+        version: version.to_string(),
+        mitigation: if parsed >= (2, 10, 0, (0, 0)) { Mitigation::NoLookupsFlag } else { Mitigation::PatchedConfig },
+    })
+}
+
+/// `2.0-beta9` as `(2, 0, 0, (1, 9))`, pre-releases ranking alpha, beta, rc, then the release.
+fn parse(version: &str) -> Option<(u32, u32, u32, (u32, u32))> {
+    let (release, pre) = version.split_once('-').unwrap_or((version, ""));
+
+    let mut numbers = release.split('.').map(|n| n.parse::<u32>());
+    let major = numbers.next()?.ok()?;
+    let minor = numbers.next().unwrap_or(Ok(0)).ok()?;
+    let patch = numbers.next().unwrap_or(Ok(0)).ok()?;
+
+    let stage = |prefix: &str| pre.strip_prefix(prefix).map(|n| n.parse::<u32>().unwrap_or(0));
+    let pre = if pre.is_empty() {
+        (3, 0)
+    } else if let Some(n) = stage("rc") {
+        (2, n)
+    } else if let Some(n) = stage("beta") {
+        (1, n)
+    } else {
+        (0, stage("alpha").unwrap_or(0))
+    };
+
+    Some((major, minor, patch, pre))
+}
+
+/// Applies `vulnerability`'s mitigation to the JVM arguments of a launch, writing the
+/// patched logging config next to the instance when one is needed.
+pub(crate) fn mitigate(vulnerability: &Vulnerability, jvm_args: &mut Vec<String>, instance_dir: &Path) -> Result<(), LaunchError> {
+    match vulnerability.mitigation {
+        Mitigation::NoLookupsFlag => {
+            jvm_args.push(NO_LOOKUPS_FLAG.to_string());
+            log::warn!("log4j-core {} is vulnerable to Log4Shell, launching with {}", vulnerability.version, NO_LOOKUPS_FLAG);
+        },
+        Mitigation::PatchedConfig => {
+            let patched = instance_dir.join("log4j2-nolookups.xml");
+
+            match jvm_args.iter_mut().find(|arg| arg.starts_with(CONFIG_ARGUMENT)) {
+                Some(arg) => {
+                    let config = PathBuf::from(&arg[CONFIG_ARGUMENT.len()..]);
+                    // the flag means nothing to these versions, so there's no launching without it
+                    if !config.is_file() {
+                        return Err(LaunchError::LoggingConfigNotFound(config))
+                    }
+
+                    std::fs::write(&patched, patch(&std::fs::read_to_string(&config)?))?;
+                    *arg = format!("{}{}", CONFIG_ARGUMENT, patched.display());
+                },
+                // the version ships no config of its own, so log4j's default would be used
+                None => {
+                    std::fs::write(&patched, FALLBACK_CONFIG)?;
+                    jvm_args.push(format!("{}{}", CONFIG_ARGUMENT, patched.display()));
+                },
+            }
+
+            log::warn!("log4j-core {} is vulnerable to Log4Shell, launching with the patched logging config {}", vulnerability.version, patched.display());
+        },
+    }
+
+    Ok(())
+}
+
+/// `config` with every message conversion in its patterns made `%msg{nolookups}`.
+pub(crate) fn patch(config: &str) -> String {
+    static MESSAGE: OnceLock<Regex> = OnceLock::new();
+    // `%m`, `%msg` and `%message`, but not the likes of `%marker`
+    let message = MESSAGE.get_or_init(|| Regex::new(r"%(?:message|msg|m)(?:\{nolookups\}|\b)").unwrap());
+
+    message.replace_all(config, "%msg{nolookups}").into_owned()
+}
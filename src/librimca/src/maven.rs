@@ -0,0 +1,239 @@
+use std::cell::{ OnceCell, RefCell };
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{ Path, PathBuf };
+use std::str::FromStr;
+use std::time::Duration;
+use nizziel::{ Download, Hash };
+use crate::error::MavenError;
+use crate::mirror::{ self, Mirror };
+
+/// Repositories tried after the one a library names itself.
+pub(crate) const REPOSITORIES: &[&str] = &[
+    "https://libraries.minecraft.net/",
+    "https://maven.fabricmc.net/",
+    "https://repo1.maven.org/maven2/",
+];
+
+/// Checksum files repositories publish next to an artifact, in order of preference.
+pub(crate) const SIDECARS: &[&str] = &["sha1", "sha256"];
+
+/// An artifact named `group:artifact:version[:classifier][@extension]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MavenCoordinate {
+    pub group: String,
+    pub artifact: String,
+    pub version: String,
+    pub classifier: Option<String>,
+    pub extension: String,
+}
+
+impl FromStr for MavenCoordinate {
+    type Err = MavenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, extension) = s.split_once('@').unwrap_or((s, "jar"));
+
+        match name.split(':').collect::<Vec<&str>>().as_slice() {
+            [group, artifact, version, rest @ ..]
+                if rest.len() <= 1 && ![group, artifact, version].iter().any(|part| part.is_empty()) && !extension.is_empty() =>
+            {
+                Ok(Self {
+                    group: group.to_string(),
+                    artifact: artifact.to_string(),
+                    version: version.to_string(),
+                    classifier: rest.first().map(|classifier| classifier.to_string()),
+                    extension: extension.to_string(),
+                })
+            },
+            _ => Err(MavenError::InvalidCoordinate(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for MavenCoordinate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.group, self.artifact, self.version)?;
+        if let Some(classifier) = &self.classifier {
+            write!(f, ":{}", classifier)?;
+        }
+        if self.extension != "jar" {
+            write!(f, "@{}", self.extension)?;
+        }
+        Ok(())
+    }
+}
+
+impl MavenCoordinate {
+    /// Where the artifact lives below a repository root, `/` separated.
+    pub fn path(&self) -> String {
+        let classifier = self.classifier.as_ref().map(|c| format!("-{}", c)).unwrap_or_default();
+
+        format!("{}/{}/{}/{}-{}{}.{}",
+            self.group.replace('.', "/"), self.artifact, self.version,
+            self.artifact, self.version, classifier, self.extension
+        )
+    }
+
+    /// `group:artifact[:classifier]`, identifying the artifact whatever its version.
+    pub fn key(&self) -> String {
+        match &self.classifier {
+            Some(classifier) => format!("{}:{}:{}", self.group, self.artifact, classifier),
+            None => format!("{}:{}", self.group, self.artifact),
+        }
+    }
+
+    /// The artifact's full URL in the repository rooted at `repository`.
+    pub fn url(&self, repository: &str) -> String {
+        format!("{}/{}", repository.trim_end_matches('/'), self.path())
+    }
+}
+
+/// Turns coordinates into downloads in the shared libraries store, trying a library's
+/// own repository before the well-known ones and pinning the checksum the repository
+/// publishes for it.
+///
+/// Only [`Resolver::pin`] goes to the network; resolving works from the checksums it cached.
+pub struct Resolver<'a> {
+    libraries: PathBuf,
+    mirrors: &'a [Mirror],
+    client: OnceCell<reqwest::blocking::Client>,
+    /// Hosts that could not be reached, not asked again for the rest of the pass.
+    unreachable: RefCell<HashSet<String>>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(libraries: &Path, mirrors: &'a [Mirror]) -> Self {
+        Self {
+            libraries: libraries.to_path_buf(),
+            mirrors,
+            client: OnceCell::new(),
+            unreachable: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// Where `coordinate` is kept in the libraries store.
+    pub fn local_path(&self, coordinate: &MavenCoordinate) -> PathBuf {
+        self.libraries.join(coordinate.path())
+    }
+
+    /// The download for `coordinate`, from `repository` if the library names one.
+    ///
+    /// It is pinned to the checksum [`Resolver::pin`] cached beside the artifact, and
+    /// goes ahead unverified without one.
+    pub fn resolve(&self, coordinate: &MavenCoordinate, repository: Option<&str>) -> Download {
+        let mut urls = repositories(repository).into_iter().map(|repo| coordinate.url(&repo));
+        let path = self.local_path(coordinate);
+
+        Download {
+            url: urls.next().unwrap_or_default(),
+            fallbacks: urls.collect(),
+            hash: cached_checksum(&path),
+            path,
+            unzip: false,
+            ..Default::default()
+        }
+    }
+
+    /// Checksum files cached beside the artifact of `coordinate`.
+    pub fn sidecars(&self, coordinate: &MavenCoordinate) -> Vec<PathBuf> {
+        let path = self.local_path(coordinate);
+        SIDECARS.iter()
+            .map(|ext| sidecar_path(&path, ext))
+            .filter(|sidecar| sidecar.is_file())
+            .collect()
+    }
+
+    /// The checksum published for `coordinate`, fetched from the repositories and cached
+    /// beside the artifact unless an earlier pin already did.
+    pub fn pin(&self, coordinate: &MavenCoordinate, repository: Option<&str>) -> Option<Hash> {
+        let path = self.local_path(coordinate);
+        if let Some(hash) = cached_checksum(&path) {
+            return Some(hash)
+        }
+
+        for repo in repositories(repository) {
+            for ext in SIDECARS {
+                let url = format!("{}.{}", coordinate.url(&repo), ext);
+                if let Some(hash) = self.fetch(&url).as_deref().and_then(|s| parse_digest(s, ext)) {
+                    let sidecar = sidecar_path(&path, ext);
+                    let written = sidecar.parent().map_or(Ok(()), std::fs::create_dir_all)
+                        .and_then(|_| std::fs::write(&sidecar, hash.expected()));
+                    if let Err(err) = written {
+                        log::warn!("Could not cache the checksum of `{}`: {}", coordinate, err);
+                    }
+                    return Some(hash)
+                }
+            }
+        }
+
+        log::debug!("No published checksum found for `{}`", coordinate);
+        None
+    }
+
+    fn fetch(&self, url: &str) -> Option<String> {
+        let client = self.client.get_or_init(|| reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default());
+
+        for candidate in mirror::candidates(self.mirrors, url) {
+            let host = reqwest::Url::parse(&candidate).ok()
+                .and_then(|url| url.host_str().map(String::from))
+                .unwrap_or_default();
+            if self.unreachable.borrow().contains(&host) {
+                continue;
+            }
+
+            match client.get(&candidate).send().and_then(|resp| resp.error_for_status()).and_then(|resp| resp.text()) {
+                Ok(body) => return Some(body),
+                Err(err) => {
+                    log::debug!("Could not fetch `{}`: {}", candidate, err);
+                    if err.is_connect() || err.is_timeout() {
+                        self.unreachable.borrow_mut().insert(host);
+                    }
+                },
+            }
+        }
+
+        None
+    }
+}
+
+/// `repository` followed by the well-known repositories, each once.
+fn repositories(repository: Option<&str>) -> Vec<String> {
+    let mut repos: Vec<String> = Vec::with_capacity(REPOSITORIES.len() + 1);
+
+    for repo in repository.into_iter().chain(REPOSITORIES.iter().copied()) {
+        let repo = format!("{}/", repo.trim_end_matches('/'));
+        if !repos.contains(&repo) {
+            repos.push(repo);
+        }
+    }
+
+    repos
+}
+
+/// `path` with the checksum extension `ext` appended, as repositories name them.
+pub(crate) fn sidecar_path(path: &Path, ext: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(ext);
+    path.with_file_name(name)
+}
+
+/// The checksum cached beside the artifact at `path` by an earlier resolve, if any.
+pub(crate) fn cached_checksum(path: &Path) -> Option<Hash> {
+    SIDECARS.iter().find_map(|ext| parse_digest(&std::fs::read_to_string(sidecar_path(path, ext)).ok()?, ext))
+}
+
+/// The hex digest at the start of a checksum file, some of which append the file name.
+fn parse_digest(contents: &str, ext: &str) -> Option<Hash> {
+    let digest = contents.split_whitespace().next()?.to_ascii_lowercase();
+    let (len, hash): (usize, fn(String) -> Hash) = match ext {
+        "sha1" => (40, Hash::Sha1),
+        _ => (64, Hash::Sha256),
+    };
+
+    (digest.len() == len && digest.bytes().all(|b| b.is_ascii_hexdigit())).then(|| hash(digest))
+}
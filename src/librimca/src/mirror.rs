@@ -0,0 +1,125 @@
+use serde::{ Serialize, Deserialize };
+use std::collections::BTreeMap;
+use std::path::Path;
+use nizziel::Downloads;
+
+/// Upstream hosts a [`Mirror::Lan`] serves, each under a path named after the host.
+pub(crate) const UPSTREAM_HOSTS: &[&str] = &[
+    "launchermeta.mojang.com",
+    "launcher.mojang.com",
+    "piston-meta.mojang.com",
+    "piston-data.mojang.com",
+    "resources.download.minecraft.net",
+    "libraries.minecraft.net",
+    "maven.fabricmc.net",
+    "meta.fabricmc.net",
+];
+
+/// Upstream hosts and the path each one lives under on a BMCLAPI-style mirror.
+const BMCLAPI_LAYOUT: &[(&str, &str)] = &[
+    ("launchermeta.mojang.com", ""),
+    ("launcher.mojang.com", ""),
+    ("piston-meta.mojang.com", ""),
+    ("piston-data.mojang.com", ""),
+    ("resources.download.minecraft.net", "/assets"),
+    ("libraries.minecraft.net", "/maven"),
+    ("maven.fabricmc.net", "/maven"),
+    ("meta.fabricmc.net", "/fabric-meta"),
+];
+
+/// A host serving copies of the upstream game files under different URLs.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Mirror {
+    /// Mirrors following the BMCLAPI layout, rooted at `base`.
+    Bmclapi { base: String },
+    /// Another rimca running `mirror serve`, rooted at `base`.
+    Lan { base: String },
+    /// Explicit rewrites from an upstream prefix such as `libraries.minecraft.net` to a replacement base URL.
+    Custom { rewrites: BTreeMap<String, String> },
+}
+
+impl Mirror {
+    /// The mirrored form of `url`, if this mirror covers its host.
+    pub fn rewrite(&self, url: &str) -> Option<String> {
+        let rest = strip_scheme(url);
+
+        match self {
+            Self::Bmclapi { base } => BMCLAPI_LAYOUT.iter()
+                .find_map(|(host, prefix)| rest.strip_prefix(host)
+                    .filter(|path| path.is_empty() || path.starts_with('/'))
+                    .map(|path| format!("{}{}{}", base.trim_end_matches('/'), prefix, path))),
+
+            Self::Lan { base } => UPSTREAM_HOSTS.iter()
+                .find(|host| rest.strip_prefix(*host).is_some_and(|path| path.starts_with('/')))
+                .map(|_| format!("{}/{}", base.trim_end_matches('/'), rest)),
+
+            // the longest matching prefix wins, so a rule for one path can override its host
+            Self::Custom { rewrites } => rewrites.iter()
+                .filter_map(|(from, to)| rest.strip_prefix(strip_scheme(from).trim_end_matches('/'))
+                    .filter(|path| path.is_empty() || path.starts_with('/'))
+                    .map(|path| (from.len(), format!("{}{}", to.trim_end_matches('/'), path))))
+                .max_by_key(|(len, _)| *len)
+                .map(|(_, url)| url),
+        }
+    }
+}
+
+pub(crate) fn strip_scheme(url: &str) -> &str {
+    url.split_once("://").map_or(url, |(_, rest)| rest)
+}
+
+/// Every URL worth trying for `url`: each mirror's rewrite in order, then the upstream itself.
+pub fn candidates(mirrors: &[Mirror], url: &str) -> Vec<String> {
+    all_candidates(mirrors, &[url.to_string()])
+}
+
+/// Every URL worth trying for any of `upstreams`, which are in order of preference: the
+/// mirrors' rewrites of each upstream, then the upstreams themselves.
+fn all_candidates(mirrors: &[Mirror], upstreams: &[String]) -> Vec<String> {
+    let rewrites = upstreams.iter().flat_map(|url| mirrors.iter().filter_map(|m| m.rewrite(url)));
+    let mut urls: Vec<String> = Vec::with_capacity((mirrors.len() + 1) * upstreams.len());
+
+    for candidate in rewrites.chain(upstreams.iter().cloned()) {
+        if !urls.contains(&candidate) {
+            urls.push(candidate);
+        }
+    }
+
+    urls
+}
+
+/// Points each download at its first candidate and keeps the rest as fallbacks, the
+/// download's own url and fallbacks all rewritten and in their original order.
+pub fn apply(mirrors: &[Mirror], dls: &mut Downloads) {
+    if mirrors.is_empty() {
+        return;
+    }
+
+    for download in &mut dls.downloads {
+        let upstreams = std::iter::once(std::mem::take(&mut download.url))
+            .chain(download.fallbacks.drain(..))
+            .collect::<Vec<String>>();
+
+        let mut urls = all_candidates(mirrors, &upstreams).into_iter();
+        download.url = urls.next().unwrap_or_default();
+        download.fallbacks = urls.collect();
+    }
+}
+
+/// Blocking fetch of `url` into `path`, walking the candidates until one succeeds.
+pub fn download(mirrors: &[Mirror], url: &str, path: &Path) -> Result<Vec<u8>, nizziel::Error> {
+    let mut last = None;
+
+    for candidate in candidates(mirrors, url) {
+        match nizziel::blocking::download(&candidate, path, false) {
+            Ok(bytes) => return Ok(bytes),
+            Err(err) => {
+                log::warn!("Could not fetch `{}`: {}", candidate, err);
+                last = Some(err);
+            }
+        }
+    }
+
+    Err(last.unwrap_or_else(|| nizziel::Error::Status { url: url.to_string(), status: 0, retry_after: None }))
+}
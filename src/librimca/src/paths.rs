@@ -1,6 +1,6 @@
 use crate::error::PathError;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{ Path, PathBuf };
 
 #[derive(Clone, Default)]
 pub struct Paths(pub HashMap<String, PathBuf>);
@@ -9,4 +9,23 @@ impl Paths {
     pub fn get(&self, key: &str) -> Result<&PathBuf, PathError> {
         self.0.get(key).ok_or_else(|| PathError::NotFound(String::from(key)))
     }
-}
\ No newline at end of file
+}
+
+/// Every file below `dir`, or none when it doesn't exist.
+pub(crate) fn files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(self::files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::io::{ BufRead, BufReader, Read, Seek, SeekFrom, Write };
+use std::net::{ TcpListener, TcpStream };
+use std::path::{ Path, PathBuf };
+use std::sync::Arc;
+use nizziel::Hash;
+use crate::error::Error;
+use crate::mirror::{ strip_scheme, UPSTREAM_HOSTS };
+use crate::vanilla::{ self, Meta };
+use crate::fabric;
+use crate::maven::{ self, MavenCoordinate };
+use crate::verify::HashIndex;
+
+/// A file the mirror may hand out, keyed by its upstream `host/path`.
+#[derive(Clone)]
+struct Entry {
+    file: PathBuf,
+    hash: Option<Hash>,
+}
+
+/// Serves the local store over HTTP as `/<upstream host>/<upstream path>`, the layout
+/// [`crate::Mirror::Lan`] rewrites to. Blocks for as long as the listener is up.
+pub fn serve(base_dir: &Path, addr: &str) -> Result<(), Error> {
+    let index = Arc::new(Index::build(base_dir));
+    let listener = TcpListener::bind(addr)?;
+    log::info!("Serving {} files from {} on {}", index.entries.len(), base_dir.display(), addr);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::warn!("Connection failed: {}", err);
+                continue;
+            }
+        };
+
+        let index = index.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = handle(&index, stream) {
+                log::debug!("Request failed: {}", err);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+struct Index {
+    entries: HashMap<String, Entry>,
+    objects: PathBuf,
+    /// Digests of the files served so far, so each is only read in full once per change.
+    /// Seeded from `hashes.json` but never written back, the store is only read from here.
+    hashes: HashIndex,
+}
+
+impl Index {
+    /// Maps every upstream URL referenced by the cached metadata to the local copy.
+    fn build(base_dir: &Path) -> Self {
+        let meta_dir = base_dir.join("meta");
+        let libraries = base_dir.join("libraries");
+        let mut entries = HashMap::new();
+        let mut insert = |url: &str, file: PathBuf, hash: Option<Hash>| {
+            entries.insert(strip_scheme(url).to_string(), Entry { file, hash });
+        };
+
+        let manifest = meta_dir.join("net.minecraft").join("version_manifest.json");
+        insert(vanilla::api::VERSION_MANIFEST_URL, manifest.clone(), None);
+
+        // version metas are only reachable through the urls listed in the manifest
+        if let Some(versions) = read_json::<serde_json::Value>(&manifest).and_then(|m| m.get("versions").cloned()) {
+            for version in versions.as_array().into_iter().flatten() {
+                if let (Some(id), Some(url)) = (version["id"].as_str(), version["url"].as_str()) {
+                    let file = meta_dir.join("net.minecraft").join(format!("{}.json", id));
+                    if file.exists() {
+                        insert(url, file, package_sha1(url).map(Hash::Sha1));
+                    }
+                }
+            }
+        }
+
+        for path in json_files(&meta_dir.join("net.minecraft")) {
+            let Some(meta) = read_json::<Meta>(&path) else { continue };
+
+            insert(
+                &meta.downloads.client.url,
+                libraries.join("com").join("mojang").join("minecraft").join(&meta.id).join(format!("minecraft-{}-client.jar", meta.id)),
+                Some(Hash::Sha1(meta.downloads.client.sha1.clone())),
+            );
+
+            insert(
+                &meta.asset_index.url,
+                base_dir.join("assets").join("indexes").join(format!("{}.json", meta.asset_index.id)),
+                meta.asset_index.sha1.clone().map(Hash::Sha1),
+            );
+
+            if let Some(config) = meta.logging.as_ref().and_then(|logging| logging.client.as_ref()) {
+                insert(
+                    &config.file.url,
+                    base_dir.join("assets").join("log_configs").join(&config.file.id),
+                    config.file.sha1.clone().map(Hash::Sha1),
+                );
+            }
+
+            for lib in &meta.libraries {
+                let artifacts = lib.downloads.artifact.iter()
+                    .chain(lib.downloads.classifiers.iter().flat_map(|c| c.values()));
+
+                for artifact in artifacts {
+                    insert(&artifact.url, libraries.join(&artifact.path), Some(Hash::Sha1(artifact.sha1.clone())));
+                }
+            }
+        }
+
+        for path in json_files(&meta_dir.join("net.fabricmc").join("loader")) {
+            if let Some(game) = path.file_stem().and_then(|s| s.to_str()) {
+                insert(&format!("{}/{}", fabric::api::MANIFEST, game), path.clone(), None);
+            }
+        }
+
+        for path in json_files(&meta_dir.join("net.fabricmc")) {
+            let Some(meta) = read_json::<fabric::models::Meta>(&path) else { continue };
+            let Some(loader) = meta.id.strip_prefix("fabric-loader-").and_then(|id| id.strip_suffix(&format!("-{}", meta.inherits_from))) else { continue };
+
+            insert(
+                &fabric::api::META.replace("{game_version}", &meta.inherits_from).replace("{loader_version}", loader),
+                path.clone(),
+                None,
+            );
+
+            for lib in &meta.libraries {
+                let Ok(coordinate) = lib.name.parse::<MavenCoordinate>() else { continue };
+                let url = coordinate.url(&lib.url);
+                let file = libraries.join(coordinate.path());
+
+                // the published checksums, so clients can pin the jars through this mirror too
+                for ext in maven::SIDECARS {
+                    let sidecar = maven::sidecar_path(&file, ext);
+                    if sidecar.exists() {
+                        insert(&format!("{}.{}", url, ext), sidecar, None);
+                    }
+                }
+
+                let hash = maven::cached_checksum(&file);
+                insert(&url, file, hash);
+            }
+        }
+
+        Self {
+            entries,
+            objects: base_dir.join("assets").join("objects"),
+            hashes: HashIndex::open(&base_dir.join("hashes.json")),
+        }
+    }
+
+    fn resolve(&self, target: &str) -> Option<Entry> {
+        let target = target.trim_start_matches('/');
+        let host = target.split('/').next()?;
+        if !UPSTREAM_HOSTS.contains(&host) {
+            return None
+        }
+
+        // asset objects are content addressed, so the name is the hash
+        if host == "resources.download.minecraft.net" {
+            let mut parts = target.split('/').skip(1);
+            if let (Some(head), Some(hash), None) = (parts.next(), parts.next(), parts.next()) {
+                if hash.len() == 40 && hash.starts_with(head) && hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return Some(Entry { file: self.objects.join(head).join(hash), hash: Some(Hash::Sha1(hash.to_string())) })
+                }
+            }
+            return None
+        }
+
+        self.entries.get(target).cloned()
+    }
+}
+
+fn handle(index: &Index, stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut range_start = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("range") {
+                range_start = value.trim()
+                    .strip_prefix("bytes=")
+                    .and_then(|r| r.strip_suffix('-'))
+                    .and_then(|r| r.parse::<u64>().ok());
+            }
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let mut stream = stream;
+
+    if method != "GET" && method != "HEAD" {
+        return respond_empty(&mut stream, "405 Method Not Allowed");
+    }
+
+    let Some(entry) = index.resolve(target.split('?').next().unwrap_or(target)) else {
+        return respond_empty(&mut stream, "404 Not Found");
+    };
+
+    if !entry.file.is_file() {
+        return respond_empty(&mut stream, "404 Not Found");
+    }
+
+    if let Some(hash) = &entry.hash {
+        if !index.hashes.digest(&entry.file, hash).is_ok_and(|found| hash.matches(&found)) {
+            log::warn!("Refusing to serve {}, its hash does not match the cached metadata", entry.file.display());
+            return respond_empty(&mut stream, "404 Not Found");
+        }
+    }
+
+    let mut file = std::fs::File::open(&entry.file)?;
+    let len = file.metadata()?.len();
+    let start = range_start.unwrap_or(0);
+
+    if range_start.is_some_and(|start| start >= len) {
+        return respond_empty(&mut stream, "416 Range Not Satisfiable");
+    }
+
+    let status = if range_start.is_some() { "206 Partial Content" } else { "200 OK" };
+    write!(stream, "HTTP/1.1 {}\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\n", status, len - start)?;
+    if range_start.is_some() {
+        write!(stream, "Content-Range: bytes {}-{}/{}\r\n", start, len.saturating_sub(1), len)?;
+    }
+    write!(stream, "Connection: close\r\n\r\n")?;
+
+    if method == "GET" {
+        file.seek(SeekFrom::Start(start))?;
+        std::io::copy(&mut file.take(len - start), &mut stream)?;
+    }
+
+    stream.flush()
+}
+
+fn respond_empty(stream: &mut TcpStream, status: &str) -> std::io::Result<()> {
+    write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status)?;
+    stream.flush()
+}
+
+/// Mojang package urls embed the sha1 of the file, `/v1/packages/<sha1>/<name>`.
+fn package_sha1(url: &str) -> Option<String> {
+    url.rsplit('/').nth(1)
+        .filter(|s| s.len() == 40 && s.bytes().all(|b| b.is_ascii_hexdigit()))
+        .map(String::from)
+}
+
+fn json_files(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir).into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+        .collect()
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let file = std::fs::File::open(path).ok()?;
+    serde_json::from_reader(BufReader::new(file)).ok()
+}
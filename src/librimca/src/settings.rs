@@ -0,0 +1,32 @@
+use serde::{ Serialize, Deserialize };
+use nizziel::DownloadPolicy;
+use crate::mirror::Mirror;
+use crate::assets::AssetFilter;
+use crate::gamelog::Level;
+
+/// Launcher wide options, usually read from the frontend's configuration file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    /// Seconds a cached version manifest or loader listing is trusted before revalidating it.
+    pub meta_ttl: u64,
+    /// Least severe game log records echoed to the terminal; the log file keeps them all.
+    pub game_log_level: Level,
+    /// Tried in order before the upstream hosts, for game files and metadata alike.
+    pub mirrors: Vec<Mirror>,
+    pub policy: DownloadPolicy,
+    /// Asset objects to download for instances that don't set their own filter.
+    pub assets: AssetFilter,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            meta_ttl: 60 * 60,
+            game_log_level: Level::default(),
+            mirrors: Vec::new(),
+            policy: DownloadPolicy::default(),
+            assets: AssetFilter::default(),
+        }
+    }
+}
@@ -4,13 +4,26 @@ use serde::{ Serialize, Deserialize };
 use std::collections::HashMap;
 use std::path::Path;
 use crate::error::StateError;
+use crate::assets::AssetFilter;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct State {
     pub scenario: String,
     pub components: HashMap<String, Component>,
     pub wrapper: Option<String>,
-    pub prelaunch_cmds: Option<Vec<String>>
+    pub prelaunch_cmds: Option<Vec<String>>,
+    /// Overrides the global asset filter for this instance.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assets: Option<AssetFilter>,
+    /// Window size to start the game with, instead of the game's default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<Resolution>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,7 +45,9 @@ impl State {
             scenario,
             components: HashMap::new(),
             wrapper: None,
-            prelaunch_cmds: None
+            prelaunch_cmds: None,
+            assets: None,
+            resolution: None,
         }
     }
 
@@ -40,6 +55,13 @@ impl State {
         self.components.get(key).ok_or_else(|| StateError::ComponentNotFound(String::from(key)))
     }
 
+    pub fn get_version(&self, key: &str) -> Result<&str, StateError> {
+        match self.get_component(key)? {
+            Component::GameComponent { version } => Ok(version),
+            _ => Err(StateError::FieldNotFound(String::from("version"), String::from(key)))
+        }
+    }
+
     pub fn write(&self, instance_path: &Path) -> Result<(), StateError> {
         let path = instance_path.join("state.json");
         let file = File::create(path)?;
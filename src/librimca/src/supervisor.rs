@@ -0,0 +1,191 @@
+use std::fs::{ File, OpenOptions };
+use std::io::{ BufRead, BufReader, IsTerminal, Read, Write };
+use std::path::{ Path, PathBuf };
+use std::process::{ Command, Stdio };
+use std::sync::{ Arc, Mutex };
+use std::thread::JoinHandle;
+use std::time::{ Duration, SystemTime };
+use chrono::{ Local, NaiveDateTime, TimeZone };
+use crate::diagnose::{ self, Diagnosis };
+use crate::gamelog::{ Echo, Level, Line, Parser };
+use crate::error::LaunchError;
+
+/// Holds the PID of the game last launched for an instance, inside its directory.
+const PID_FILE: &str = "rimca.pid";
+
+/// Names each launch's log after when it started.
+const LOG_NAME: &str = "rimca-%Y-%m-%d_%H-%M-%S.log";
+
+/// Whether rimca stays around for the game or hands it off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LaunchMode {
+    /// Waits for the game, teeing its output into the instance's logs and passing on
+    /// interrupts, and reports how it exited.
+    #[default]
+    Attached,
+    /// Leaves the game running on its own, its output going straight to the log.
+    Detached,
+}
+
+/// How a launch ended, as far as rimca saw it.
+#[derive(Debug)]
+pub enum Launched {
+    /// The game exited with `code`, or none when a signal killed it. Unless it exited
+    /// cleanly, the crash files it left behind have been looked at.
+    Exited { code: Option<i32>, log: PathBuf, diagnosis: Option<Diagnosis> },
+    /// The game is still running as `pid`.
+    Detached { pid: u32, log: PathBuf },
+}
+
+/// Spawns `command` in `instance_dir` the way `mode` asks, logging its output to
+/// `logs/rimca-<timestamp>.log` and echoing records from `echo` up to the terminal too.
+pub(crate) fn run(mut command: Command, instance_dir: &Path, mode: LaunchMode, echo: Option<Level>) -> Result<Launched, LaunchError> {
+    // file times come from a coarse clock, so a report written right away can look older
+    let started = SystemTime::now() - Duration::from_secs(1);
+    let log = instance_dir.join("logs").join(Local::now().format(LOG_NAME).to_string());
+    std::fs::create_dir_all(instance_dir.join("logs"))?;
+    let file = OpenOptions::new().create(true).append(true).open(&log)?;
+
+    // in a group of its own the game only sees the interrupts rimca passes on, and
+    // outlives the terminal when detached
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    command.stdin(Stdio::null());
+
+    if mode == LaunchMode::Detached {
+        command.stdout(file.try_clone()?).stderr(file);
+
+        log::debug!("Spawning command: {:?}", command);
+        let pid = command.spawn()?.id();
+        std::fs::write(instance_dir.join(PID_FILE), pid.to_string())?;
+
+        log::info!("Game detached as process {}, logging to {}", pid, log.display());
+        return Ok(Launched::Detached { pid, log })
+    }
+
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    log::debug!("Spawning command: {:?}", command);
+    let mut child = command.spawn()?;
+    std::fs::write(instance_dir.join(PID_FILE), child.id().to_string())?;
+    log::info!("Game running as process {}, logging to {}", child.id(), log.display());
+
+    let stop_forwarding = forward_signals(child.id())?;
+    let file = Arc::new(Mutex::new(file));
+    let tees = [
+        child.stdout.take().map(|out| tee(out, file.clone(), echo.map(|level| Echo {
+            colour: std::io::stdout().is_terminal(),
+            out: Box::new(std::io::stdout()),
+            level,
+        }))),
+        child.stderr.take().map(|err| tee(err, file.clone(), echo.map(|level| Echo {
+            colour: std::io::stderr().is_terminal(),
+            out: Box::new(std::io::stderr()),
+            level,
+        }))),
+    ];
+
+    let status = child.wait();
+    stop_forwarding();
+    for tee in tees.into_iter().flatten() {
+        let _ = tee.join();
+    }
+    let _ = std::fs::remove_file(instance_dir.join(PID_FILE));
+
+    let code = status?.code();
+    match code {
+        Some(0) => log::info!("Game exited normally"),
+        Some(code) => log::warn!("Game exited with code {}", code),
+        None => log::warn!("Game was killed by a signal"),
+    }
+
+    let diagnosis = match code {
+        Some(0) => None,
+        _ => Some(diagnose::scan(instance_dir, started, Some(&log))?),
+    };
+
+    Ok(Launched::Exited { code, log, diagnosis })
+}
+
+/// The log of the last launch in `instance_dir` and when that launch started.
+pub(crate) fn last_launch(instance_dir: &Path) -> Option<(PathBuf, SystemTime)> {
+    std::fs::read_dir(instance_dir.join("logs")).ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let started = NaiveDateTime::parse_from_str(entry.file_name().to_str()?, LOG_NAME).ok()?;
+            Some((entry.path(), SystemTime::from(Local.from_local_datetime(&started).earliest()?)))
+        })
+        .max_by_key(|(_, started)| *started)
+}
+
+/// The game process recorded for the instance in `instance_dir`, if it's still running.
+pub(crate) fn running(instance_dir: &Path) -> Option<u32> {
+    let pid = std::fs::read_to_string(instance_dir.join(PID_FILE)).ok()?.trim().parse().ok()?;
+    is_alive(pid).then_some(pid)
+}
+
+/// Copies `source` line by line into the shared log and `echo`, turning log4j events
+/// into readable records on the way.
+fn tee(source: impl Read + Send + 'static, log: Arc<Mutex<File>>, mut echo: Option<Echo>) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(source);
+        let mut parser = Parser::default();
+        let mut buf = Vec::new();
+
+        let mut write = |line: Line| {
+            if let Ok(mut log) = log.lock() {
+                let _ = match &line {
+                    Line::Record(record) => log.write_all(record.format().as_bytes()),
+                    Line::Text(text) => log.write_all(text.as_bytes()),
+                };
+            }
+            if let Some(echo) = &mut echo {
+                let _ = echo.write(&line);
+            }
+        };
+
+        while reader.read_until(b'\n', &mut buf).is_ok_and(|read| read > 0) {
+            parser.push(&String::from_utf8_lossy(&buf)).into_iter().for_each(&mut write);
+            buf.clear();
+        }
+        parser.finish().into_iter().for_each(write);
+    })
+}
+
+/// Passes SIGINT and SIGTERM on to `pid` until the returned closure is called.
+#[cfg(unix)]
+fn forward_signals(pid: u32) -> std::io::Result<impl FnOnce()> {
+    use signal_hook::consts::{ SIGINT, SIGTERM };
+
+    let mut signals = signal_hook::iterator::Signals::new([SIGINT, SIGTERM])?;
+    let handle = signals.handle();
+    let thread = std::thread::spawn(move || {
+        for signal in signals.forever() {
+            log::info!("Passing signal {} on to the game", signal);
+            // SAFETY: kill has no memory safety requirements
+            unsafe { libc::kill(pid as libc::pid_t, signal) };
+        }
+    });
+
+    Ok(move || {
+        handle.close();
+        let _ = thread.join();
+    })
+}
+
+/// The console delivers interrupts to the game directly.
+#[cfg(not(unix))]
+fn forward_signals(_pid: u32) -> std::io::Result<impl FnOnce()> {
+    Ok(|| ())
+}
+
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+/// Without a cheap check the recorded PID is taken at its word.
+#[cfg(not(unix))]
+fn is_alive(_pid: u32) -> bool {
+    true
+}
@@ -0,0 +1,408 @@
+use crate::mirror::{ self, Mirror };
+
+#[test]
+fn test_mirror_rewrite() {
+    let bmclapi = Mirror::Bmclapi { base: "https://bmclapi2.bangbang93.com/".to_string() };
+    let lan = Mirror::Custom {
+        rewrites: [("libraries.minecraft.net".to_string(), "http://10.0.0.2:8080/libraries/".to_string())].into(),
+    };
+
+    let url = "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar";
+    assert_eq!(mirror::candidates(&[lan, bmclapi.clone()], url), vec![
+        "http://10.0.0.2:8080/libraries/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar",
+        "https://bmclapi2.bangbang93.com/maven/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar",
+        url,
+    ]);
+
+    assert_eq!(
+        bmclapi.rewrite("http://launchermeta.mojang.com/mc/game/version_manifest.json").as_deref(),
+        Some("https://bmclapi2.bangbang93.com/mc/game/version_manifest.json")
+    );
+    assert_eq!(bmclapi.rewrite("https://libraries.minecraft.net.example.com/x.jar"), None);
+
+    // a library's own repository stays first, and its fallbacks are mirrored too
+    let lan_fabric = Mirror::Custom {
+        rewrites: [
+            ("libraries.minecraft.net".to_string(), "http://10.0.0.2:8080/libraries/".to_string()),
+            ("maven.fabricmc.net".to_string(), "http://10.0.0.2:8080/fabric/".to_string()),
+        ].into(),
+    };
+    let mut dls = nizziel::Downloads::default();
+    dls.downloads.push(nizziel::Download {
+        url: "https://maven.fabricmc.net/net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar".to_string(),
+        fallbacks: vec![
+            "https://libraries.minecraft.net/net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar".to_string(),
+            "https://repo1.maven.org/maven2/net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar".to_string(),
+        ],
+        ..Default::default()
+    });
+    mirror::apply(&[lan_fabric], &mut dls);
+    assert_eq!(dls.downloads[0].url, "http://10.0.0.2:8080/fabric/net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar");
+    assert_eq!(dls.downloads[0].fallbacks, [
+        "http://10.0.0.2:8080/libraries/net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar",
+        "https://maven.fabricmc.net/net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar",
+        "https://libraries.minecraft.net/net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar",
+        "https://repo1.maven.org/maven2/net/fabricmc/intermediary/1.20.1/intermediary-1.20.1.jar",
+    ]);
+
+    let rimca = Mirror::Lan { base: "http://192.168.1.20:8765".to_string() };
+    assert_eq!(
+        rimca.rewrite("https://resources.download.minecraft.net/cf/cfd7e5a073152eefdc3bd8b85137b333724982a7").as_deref(),
+        Some("http://192.168.1.20:8765/resources.download.minecraft.net/cf/cfd7e5a073152eefdc3bd8b85137b333724982a7")
+    );
+}
+
+#[test]
+fn test_hash_index_reuse() {
+    use crate::verify::HashIndex;
+    use nizziel::Hash;
+
+    let dir = std::env::temp_dir().join(format!("rimca-hash-index-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("object");
+    let hashes = dir.join("hashes.json");
+    std::fs::write(&file, b"hello").unwrap();
+
+    let sha1 = Hash::Sha1("aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d".to_string());
+    let index = HashIndex::open(&hashes);
+    assert_eq!(index.digest(&file, &sha1).unwrap(), sha1.expected());
+    index.save().unwrap();
+
+    // a recorded digest is trusted while size and mtime hold, and dropped once they change
+    let index = HashIndex::open(&hashes);
+    index.record(&file, &Hash::Sha1("0".repeat(40)));
+    assert_eq!(index.digest(&file, &sha1).unwrap(), "0".repeat(40));
+
+    // a fresh index rehashes regardless, and keeps what it found for the next
+    index.save().unwrap();
+    let fresh = HashIndex::fresh(&hashes);
+    assert_eq!(fresh.digest(&file, &sha1).unwrap(), sha1.expected());
+    fresh.save().unwrap();
+    assert_eq!(HashIndex::open(&hashes).digest(&file, &sha1).unwrap(), sha1.expected());
+
+    std::fs::write(&file, b"hello world").unwrap();
+    assert_eq!(index.digest(&file, &sha1).unwrap(), "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_gc() {
+    use crate::Settings;
+
+    let base = std::env::temp_dir().join(format!("rimca-gc-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&base);
+    let write = |path: &str, contents: &str| {
+        let path = base.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, contents).unwrap();
+        path
+    };
+
+    // a fabric instance on a legacy version, whose assets are also wanted by name
+    write("instances/test/state.json", r#"{"scenario": "fabric", "components": {"net.minecraft": {"version": "1.7.10"}, "net.fabricmc": {"version": "0.15.0"}}, "wrapper": null, "prelaunch_cmds": null}"#);
+    let meta = [
+        write("meta/net.minecraft/1.7.10.json", r#"{
+            "minecraftArguments": "--username ${auth_player_name}",
+            "assetIndex": {"id": "legacy", "url": "https://example.com/legacy.json"},
+            "assets": "legacy",
+            "downloads": {"client": {"url": "https://example.com/client.jar", "sha1": "0000000000000000000000000000000000000000"}},
+            "id": "1.7.10",
+            "libraries": [{"name": "com.example:lib:1.0", "downloads": {"artifact": {
+                "url": "https://example.com/lib-1.0.jar", "path": "com/example/lib/1.0/lib-1.0.jar", "sha1": "0000000000000000000000000000000000000000"
+            }}}],
+            "mainClass": "net.minecraft.client.main.Main",
+            "type": "release"
+        }"#),
+        write("meta/net.fabricmc/fabric-loader-0.15.0-1.7.10.json", r#"{
+            "id": "fabric-loader-0.15.0-1.7.10", "inheritsFrom": "1.7.10", "releaseTime": "", "time": "", "type": "release",
+            "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+            "libraries": [{"name": "net.fabricmc:intermediary:1.7.10", "url": "https://maven.fabricmc.net/"}]
+        }"#),
+        write("assets/indexes/legacy.json", r#"{"virtual": true, "objects": {"sounds/a.ogg": {"hash": "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d", "size": 5}}}"#),
+    ];
+    let kept = [
+        write("libraries/com/mojang/minecraft/1.7.10/minecraft-1.7.10-client.jar", "client"),
+        write("libraries/com/example/lib/1.0/lib-1.0.jar", "lib"),
+        write("libraries/net/fabricmc/intermediary/1.7.10/intermediary-1.7.10.jar", "intermediary"),
+        write("libraries/net/fabricmc/intermediary/1.7.10/intermediary-1.7.10.jar.sha1", "0000000000000000000000000000000000000000"),
+        write("assets/objects/aa/aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d", "hello"),
+        write("assets/virtual/legacy/sounds/a.ogg", "hello"),
+        write("meta/net.minecraft/version_manifest.json", "{}"),
+        write("meta/net.minecraft/version_manifest.json.cache", "{}"),
+    ];
+    let unreferenced = write("libraries/com/example/old/1.0/old-1.0.jar", "old");
+
+    let report = crate::gc(&base, true, &Settings::default()).unwrap();
+    assert_eq!(report.files, std::slice::from_ref(&unreferenced));
+    assert_eq!(report.bytes, 3);
+    assert!(unreferenced.exists());
+
+    let report = crate::gc(&base, false, &Settings::default()).unwrap();
+    assert_eq!(report.files, std::slice::from_ref(&unreferenced));
+    assert!(!base.join("libraries/com/example/old").exists());
+    assert!(meta.iter().chain(&kept).all(|path| path.exists()));
+
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn test_asset_filter() {
+    use crate::assets::{ glob, AssetFilter, AssetPreset };
+
+    assert!(glob("minecraft/sounds/music/**", "minecraft/sounds/music/game/calm1.ogg"));
+    assert!(glob("minecraft/lang/*", "minecraft/lang/de_de.json"));
+    assert!(!glob("minecraft/lang/*", "minecraft/lang/sub/de_de.json"));
+    assert!(glob("a/**/b", "a/b"));
+    assert!(!glob("a/**/b", "a/xb"));
+
+    let minimal = AssetFilter::from(AssetPreset::Minimal);
+    assert!(!minimal.allows("minecraft/sounds/music/game/calm1.ogg"));
+    assert!(!minimal.allows("minecraft/lang/de_de.json"));
+    assert!(minimal.allows("minecraft/lang/en_us.json"));
+    assert!(minimal.allows("minecraft/sounds/mob/cow/say1.ogg"));
+
+    let custom = AssetFilter { exclude: vec!["**.ogg".to_string()], include: vec!["minecraft/sounds/ui/*".to_string()], ..Default::default() };
+    assert!(!custom.allows("minecraft/sounds/mob/cow/say1.ogg"));
+    assert!(custom.allows("minecraft/sounds/ui/click.ogg"));
+    assert!(AssetFilter::default().allows("minecraft/sounds/music/game/calm1.ogg"));
+}
+
+#[test]
+fn test_argument_rules() {
+    use crate::vanilla::models::Argument;
+    use crate::vanilla::rules::Environment;
+
+    let arguments: Vec<Argument> = serde_json::from_str(r#"[
+        "--username", "${auth_player_name}",
+        { "rules": [{ "action": "allow", "features": { "is_demo_user": true } }], "value": "--demo" },
+        { "rules": [{ "action": "allow", "features": { "has_custom_resolution": true } }], "value": ["--width", "${resolution_width}"] },
+        { "rules": [{ "action": "allow", "os": { "name": "osx" } }], "value": ["-XstartOnFirstThread"] },
+        { "rules": [{ "action": "allow", "os": { "name": "windows", "version": "^10\\." } }], "value": ["-Dos.name=Windows 10"] },
+        { "rules": [{ "action": "allow", "os": { "arch": "x86" } }], "value": "-Xss1M" }
+    ]"#).unwrap();
+
+    let env = Environment {
+        os_name: "windows".to_string(),
+        os_arch: "x86_64".to_string(),
+        os_version: "10.0.19045".to_string(),
+        ..Default::default()
+    }.with_feature("has_custom_resolution", true);
+
+    let values = arguments.iter().flat_map(|arg| arg.values(&env)).collect::<Vec<&str>>();
+    assert_eq!(values, ["--username", "${auth_player_name}", "--width", "${resolution_width}", "-Dos.name=Windows 10"]);
+
+    let env = Environment { os_name: "osx".to_string(), os_arch: "x86".to_string(), ..Default::default() };
+    let values = arguments.iter().flat_map(|arg| arg.values(&env)).collect::<Vec<&str>>();
+    assert_eq!(values, ["--username", "${auth_player_name}", "-XstartOnFirstThread", "-Xss1M"]);
+}
+
+#[test]
+fn test_library_rules() {
+    use crate::vanilla::models::Library;
+    use crate::vanilla::rules::Environment;
+
+    let library: Library = serde_json::from_str(r#"{
+        "name": "org.lwjgl:lwjgl:3.3.1:natives-macos-arm64",
+        "downloads": {},
+        "rules": [{ "action": "allow", "os": { "name": "osx" } }, { "action": "disallow", "os": { "arch": "x86_64" } }]
+    }"#).unwrap();
+
+    let env = |os_name: &str, os_arch: &str| Environment { os_name: os_name.to_string(), os_arch: os_arch.to_string(), ..Default::default() };
+    assert!(library.is_allowed(&env("osx", "arm64")));
+    assert!(!library.is_allowed(&env("osx", "x86_64")));
+    assert!(!library.is_allowed(&env("linux", "arm64")));
+}
+
+#[test]
+fn test_native_classifiers() {
+    use crate::vanilla::models::Library;
+
+    let library: Library = serde_json::from_str(r#"{
+        "downloads": {
+            "classifiers": {
+                "natives-linux": { "path": "org/lwjgl/lwjgl/lwjgl-platform/2.9.4-nightly-20150209/lwjgl-platform-2.9.4-nightly-20150209-natives-linux.jar", "sha1": "931074f46c795d2f7b30ed6395df5715cfd7675b", "size": 578680, "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/lwjgl-platform/2.9.4-nightly-20150209/lwjgl-platform-2.9.4-nightly-20150209-natives-linux.jar" },
+                "natives-osx": { "path": "org/lwjgl/lwjgl/lwjgl-platform/2.9.4-nightly-20150209/lwjgl-platform-2.9.4-nightly-20150209-natives-osx.jar", "sha1": "bcab850f8f487c3f4c4dbabde778bb82bd1a40ed", "size": 426822, "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/lwjgl-platform/2.9.4-nightly-20150209/lwjgl-platform-2.9.4-nightly-20150209-natives-osx.jar" },
+                "natives-windows": { "path": "org/lwjgl/lwjgl/lwjgl-platform/2.9.4-nightly-20150209/lwjgl-platform-2.9.4-nightly-20150209-natives-windows.jar", "sha1": "b84d5102b9dbfabfeb5e43c7e2828d98a7fc80e0", "size": 613748, "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/lwjgl-platform/2.9.4-nightly-20150209/lwjgl-platform-2.9.4-nightly-20150209-natives-windows.jar" }
+            }
+        },
+        "extract": { "exclude": ["META-INF/"] },
+        "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209",
+        "natives": { "linux": "natives-linux", "osx": "natives-osx", "windows": "natives-windows" }
+    }"#).unwrap();
+
+    let natives = library.natives.as_ref().unwrap();
+    assert_eq!(natives.macos.as_deref(), Some("natives-osx"));
+
+    let platform = match std::env::consts::OS {
+        "macos" => "osx",
+        os => os,
+    };
+    let jar = crate::vanilla::natives::classifier(&library).unwrap().unwrap();
+    assert!(jar.path.ends_with(&format!("-natives-{}.jar", platform)));
+}
+
+#[test]
+fn test_classpath_overrides() {
+    use crate::classpath::{ Classpath, SEPARATOR };
+    use std::path::PathBuf;
+
+    let mut classpath = Classpath::default();
+    classpath.push("org.ow2.asm:asm:9.3", PathBuf::from("asm-9.3.jar"));
+    classpath.push("org.lwjgl:lwjgl:3.3.1", PathBuf::from("lwjgl-3.3.1.jar"));
+    classpath.push("org.lwjgl:lwjgl:3.3.1:natives-linux", PathBuf::from("lwjgl-3.3.1-natives-linux.jar"));
+    classpath.push("org.ow2.asm:asm:9.6", PathBuf::from("asm-9.6.jar"));
+
+    let expected = ["asm-9.6.jar", "lwjgl-3.3.1.jar", "lwjgl-3.3.1-natives-linux.jar"].join(&SEPARATOR.to_string());
+    assert_eq!(classpath.build(), expected);
+}
+
+#[test]
+fn test_maven_coordinates() {
+    use crate::maven::MavenCoordinate;
+
+    let plain: MavenCoordinate = "net.fabricmc:fabric-loader:0.15.6".parse().unwrap();
+    assert_eq!(plain.path(), "net/fabricmc/fabric-loader/0.15.6/fabric-loader-0.15.6.jar");
+    assert_eq!(plain.key(), "net.fabricmc:fabric-loader");
+    assert_eq!(plain.url("https://maven.fabricmc.net/"), "https://maven.fabricmc.net/net/fabricmc/fabric-loader/0.15.6/fabric-loader-0.15.6.jar");
+
+    let native: MavenCoordinate = "org.lwjgl:lwjgl:3.3.1:natives-linux".parse().unwrap();
+    assert_eq!(native.path(), "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar");
+    assert_eq!(native.key(), "org.lwjgl:lwjgl:natives-linux");
+
+    let zip: MavenCoordinate = "de.oceanlabs.mcp:mcp_config:1.20.1@zip".parse().unwrap();
+    assert_eq!(zip.path(), "de/oceanlabs/mcp/mcp_config/1.20.1/mcp_config-1.20.1.zip");
+    assert_eq!(zip.to_string(), "de.oceanlabs.mcp:mcp_config:1.20.1@zip");
+
+    assert!("com.mojang:minecraft".parse::<MavenCoordinate>().is_err());
+    assert!("a:b:c:d:e".parse::<MavenCoordinate>().is_err());
+    assert!("a::c".parse::<MavenCoordinate>().is_err());
+}
+
+#[test]
+fn test_crash_diagnosis() {
+    use std::time::{ Duration, SystemTime };
+
+    let dir = std::env::temp_dir().join(format!("rimca-diagnose-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("crash-reports")).unwrap();
+    std::fs::write(dir.join("crash-reports").join("crash-2024-01-01_12.00.00-client.txt"), "\
+---- Minecraft Crash Report ----
+// Oops.
+
+Time: 2024-01-01 12:00:00
+Description: Initializing game
+
+java.lang.OutOfMemoryError: Java heap space
+\tat net.minecraft.class_310.handler$zbc000$sodium$init(class_310.java:42) [sodium.mixins.json]
+
+Suspected Mods: 
+\tExample Mod (examplemod), Version: 1.0
+\t\tIssue tracker URL: https://example.com
+").unwrap();
+    std::fs::write(dir.join("hs_err_pid4242.log"), "\
+#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  SIGSEGV (0xb) at pc=0x00007f3c1c2b5a40, pid=4242, tid=4243
+#
+# Problematic frame:
+# C  [liblwjgl.so+0x1a40]
+").unwrap();
+
+    let diagnosis = crate::diagnose::scan(&dir, SystemTime::now() - Duration::from_secs(60), None).unwrap();
+    assert_eq!(diagnosis.reports.len(), 2);
+    assert_eq!(diagnosis.reports[0].headline.as_deref(), Some("java.lang.OutOfMemoryError: Java heap space"));
+    assert_eq!(diagnosis.reports[0].suspected_mods, ["Example Mod (examplemod)", "sodium"]);
+    assert_eq!(diagnosis.reports[1].headline.as_deref(), Some("SIGSEGV (0xb) at pc=0x00007f3c1c2b5a40, pid=4242, tid=4243 in C  [liblwjgl.so+0x1a40]"));
+    assert_eq!(diagnosis.hints.len(), 1);
+
+    // reports from before the launch are someone else's crash
+    let diagnosis = crate::diagnose::scan(&dir, SystemTime::now() + Duration::from_secs(60), None).unwrap();
+    assert!(diagnosis.is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_log4j_events() {
+    use crate::gamelog::{ Level, Line, Parser };
+
+    let output = "\
+Starting game
+<log4j:Event logger=\"net.minecraft.client.Minecraft\" timestamp=\"1700000000000\" level=\"WARN\" thread=\"Render thread\">
+  <log4j:Message><![CDATA[Missing sound for event: <none> & more]]></log4j:Message>
+</log4j:Event>
+<log4j:Event logger=\"net.minecraft.server.Main\" timestamp=\"1700000000001\" level=\"ERROR\" thread=\"main\">
+  <log4j:Message><![CDATA[Crashed]]></log4j:Message>
+  <log4j:Throwable><![CDATA[java.lang.IllegalStateException
+\tat net.minecraft.server.Main.main(Main.java:1)
+]]></log4j:Throwable>
+</log4j:Event>
+<log4j:Event logger=\"unfinished\"
+";
+
+    let mut parser = Parser::default();
+    let mut lines = output.split_inclusive('\n').flat_map(|line| parser.push(line)).collect::<Vec<Line>>();
+    lines.extend(parser.finish());
+
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], Line::Text("Starting game\n".to_string()));
+
+    let Line::Record(warn) = &lines[1] else { panic!("expected a record, got {:?}", lines[1]) };
+    assert_eq!((warn.level, warn.logger.as_str(), warn.thread.as_str()), (Level::Warn, "net.minecraft.client.Minecraft", "Render thread"));
+    assert_eq!(warn.message, "Missing sound for event: <none> & more");
+    assert!(warn.format().ends_with("[Render thread/WARN] (Minecraft) Missing sound for event: <none> & more\n"));
+
+    let Line::Record(error) = &lines[2] else { panic!("expected a record, got {:?}", lines[2]) };
+    assert_eq!(error.level, Level::Error);
+    assert!(error.throwable.as_deref().is_some_and(|t| t.starts_with("java.lang.IllegalStateException\n\tat ")));
+
+    assert_eq!(lines[3], Line::Text("<log4j:Event logger=\"unfinished\"\n".to_string()));
+    assert!(Level::Debug < Level::Info && Level::Error < Level::Fatal);
+}
+
+#[test]
+fn test_log4j_mitigation() {
+    use crate::error::LaunchError;
+    use crate::log4j::{ check, mitigate, patch, scan, Mitigation };
+    use std::path::Path;
+
+    assert_eq!(check("2.0-beta9").map(|v| v.mitigation), Some(Mitigation::PatchedConfig));
+    assert_eq!(check("2.8.1").map(|v| v.mitigation), Some(Mitigation::PatchedConfig));
+    assert_eq!(check("2.14.1").map(|v| v.mitigation), Some(Mitigation::NoLookupsFlag));
+    assert_eq!(check("2.10.0").map(|v| v.mitigation), Some(Mitigation::NoLookupsFlag));
+    assert!(check("2.0-beta8").is_none());
+    assert!(check("2.17.0").is_none());
+
+    let libraries = [
+        Path::new("libraries/org/apache/logging/log4j/log4j-api/2.8.1/log4j-api-2.8.1.jar"),
+        Path::new("libraries/org/apache/logging/log4j/log4j-core/2.8.1/log4j-core-2.8.1.jar"),
+    ];
+    assert_eq!(scan(libraries).map(|v| v.version), Some("2.8.1".to_string()));
+
+    assert_eq!(
+        patch(r#"<PatternLayout pattern="[%d] %marker %m%n"/><PatternLayout pattern="%msg{nolookups}%n %message"/>"#),
+        r#"<PatternLayout pattern="[%d] %marker %msg{nolookups}%n"/><PatternLayout pattern="%msg{nolookups}%n %msg{nolookups}"/>"#,
+    );
+
+    // instances installed before logging configs were downloaded have none to patch
+    let dir = std::env::temp_dir().join(format!("rimca-log4j-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("client-1.7.xml");
+    let mut jvm_args = vec![format!("-Dlog4j.configurationFile={}", config.display())];
+    let vulnerability = check("2.0-beta9").unwrap();
+    assert!(matches!(mitigate(&vulnerability, &mut jvm_args, &dir), Err(LaunchError::LoggingConfigNotFound(_))));
+
+    std::fs::write(&config, r#"<PatternLayout pattern="%m%n"/>"#).unwrap();
+    mitigate(&vulnerability, &mut jvm_args, &dir).unwrap();
+    assert_eq!(jvm_args, [format!("-Dlog4j.configurationFile={}", dir.join("log4j2-nolookups.xml").display())]);
+
+    // versions without a config of their own get a console-only one
+    let mut jvm_args = vec!["-Xmx2G".to_string()];
+    mitigate(&vulnerability, &mut jvm_args, &dir).unwrap();
+    assert_eq!(jvm_args[1], format!("-Dlog4j.configurationFile={}", dir.join("log4j2-nolookups.xml").display()));
+    let fallback = std::fs::read_to_string(dir.join("log4j2-nolookups.xml")).unwrap();
+    assert!(fallback.contains("%msg{nolookups}") && patch(&fallback) == fallback);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,7 +1,8 @@
 use serde::{ Serialize, Deserialize };
 use crate::error::ApiError;
+use crate::cache::MetaCache;
 
-const VERSION_MANIFEST_URL: &str = "http://launchermeta.mojang.com/mc/game/version_manifest.json";
+pub(crate) const VERSION_MANIFEST_URL: &str = "http://launchermeta.mojang.com/mc/game/version_manifest.json";
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -19,21 +20,18 @@ pub struct Manifest {
     versions: Vec<Version>
 }
 
-pub fn versions(snapshots: bool) -> Result<Vec<Version>, ApiError> {
-    let manifest = reqwest::blocking::get(VERSION_MANIFEST_URL)?
-        .json::<Manifest>().unwrap();
+fn manifest(cache: &MetaCache) -> Result<Manifest, ApiError> {
+    let bytes = cache.get(VERSION_MANIFEST_URL, "net.minecraft/version_manifest.json")?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
 
-    Ok(manifest.versions
+pub fn versions(cache: &MetaCache, snapshots: bool) -> Result<Vec<Version>, ApiError> {
+    Ok(manifest(cache)?.versions
         .into_iter()
         .filter(|v| !v.r#type.eq("snapshot") || snapshots)
         .collect())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct VanillaLatest {
-    pub latest: Latest,
-}
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Latest {
     pub release: String,
@@ -41,12 +39,13 @@ pub struct Latest {
 }
 
 
-pub fn latest(snapshot: bool) -> Result<Version, ApiError> {
-    let van = reqwest::blocking::get(VERSION_MANIFEST_URL)?
-        .json::<VanillaLatest>()?;
+pub fn latest(cache: &MetaCache, snapshot: bool) -> Result<Version, ApiError> {
+    let manifest = manifest(cache)?;
+    let latest = manifest.latest;
 
-    versions(snapshot)?
+    manifest.versions
         .into_iter()
-        .find(|v| v.id.eq(&van.latest.release) || v.id.eq(&van.latest.snapshot))
+        .filter(|v| !v.r#type.eq("snapshot") || snapshot)
+        .find(|v| v.id.eq(&latest.release) || v.id.eq(&latest.snapshot))
         .ok_or(ApiError::CannotFindLatestVersion)
 }
\ No newline at end of file
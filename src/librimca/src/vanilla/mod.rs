@@ -2,150 +2,291 @@ pub mod api;
 
 pub mod models;
 
+pub mod rules;
+
+pub(crate) mod natives;
+
 pub use models::{Meta, Assets};
+use models::LoggingConfig;
 
 use crate::{Instance, Paths};
+use crate::cache::MetaCache;
+use crate::mirror;
 use crate::download::DownloadSequence;
 use crate::launch::LaunchSequence;
-use crate::error::{LaunchError, LaunchArguments, DownloadError, StateError};
+use crate::error::{LaunchError, LaunchArguments, DownloadError, StateError, PathError};
 use crate::state::Component;
-use crate::verify::is_file_valid;
-use crate::vanilla::api::Version;
+use crate::assets::AssetFilter;
+use crate::classpath::Classpath;
+use crate::maven::MavenCoordinate;
 
 use std::io::BufReader;
 use std::path::PathBuf;
-use nizziel::{Download, Downloads};
-use crate::vanilla::models::Library;
+use nizziel::{Download, Downloads, Hash};
+use rules::Environment;
 
 pub struct Vanilla {
-    pub version: Version,
     pub meta: Meta,
 }
 
 impl Vanilla {
-    pub fn new(paths: &Paths, version: Option<String>) -> Result<Self, DownloadError> {
+    /// Resolves `version` against the version manifest, fetching its meta if it isn't cached yet.
+    pub fn new(paths: &Paths, cache: &MetaCache, version: Option<String>) -> Result<Self, DownloadError> {
         let version = match &version {
             Some(ver) => {
-                api::versions(true)?.into_iter().find(|v| v.id.eq(ver))
+                api::versions(cache, true)?.into_iter().find(|v| v.id.eq(ver))
                     .ok_or_else(|| DownloadError::GameVersionNotFound(ver.to_string()))?
             }
 
-            None => api::latest(false)?
+            None => api::latest(cache, false)?
         };
 
-        let meta = {
-            let path = paths.get("meta")?.join("net.minecraft").join(format!("{}.json", &version.id));
-            if let Ok(file) = std::fs::File::open(&path) {
-                let reader = BufReader::new(file);
-                serde_json::from_reader(reader)?
-            } else {
-                let meta_str = nizziel::blocking::download(&version.url, &path, false)?;
-                serde_json::from_slice::<Meta>(&meta_str)?
-            }
+        let path = meta_path(paths, &version.id)?;
+        let meta = if let Ok(file) = std::fs::File::open(&path) {
+            let reader = BufReader::new(file);
+            serde_json::from_reader(reader)?
+        } else {
+            let meta_str = mirror::download(cache.mirrors(), &version.url, &path)?;
+            serde_json::from_slice::<Meta>(&meta_str)?
         };
 
-        Ok(Self {
-            version,
-            meta,
-        })
+        Ok(Self { meta })
+    }
+
+    /// Loads `version` from the local meta cache only, never touching the network.
+    pub fn read(paths: &Paths, version: &str) -> Result<Self, LaunchError> {
+        let path = meta_path(paths, version)?;
+        let file = std::fs::File::open(&path)
+            .map_err(|_| LaunchError::MetaNotFound(String::from("net.minecraft"), version.to_string()))?;
+        let meta = serde_json::from_reader(BufReader::new(file))?;
+
+        Ok(Self { meta })
     }
 }
 
-fn process_natives(key_option: Option<&String>, natives_dir: PathBuf, lib: &Library, dls: &mut Downloads) -> Result<(), DownloadError> {
-    if let Some(key) = key_option {
-        if let Some(url) = lib.downloads.classifiers.as_ref().ok_or_else(|| DownloadError::LibraryNoClassifiers(lib.name.clone()))?.get(key) {
-            dls.downloads.push(Download {
-                url: url.url.to_string(),
-                path: natives_dir.clone(),
-                unzip: true,
-            });
+fn meta_path(paths: &Paths, version: &str) -> Result<PathBuf, PathError> {
+    Ok(paths.get("meta")?.join("net.minecraft").join(format!("{}.json", version)))
+}
+
+impl Instance<Vanilla> {
+    fn asset_index_path(&self) -> Result<PathBuf, PathError> {
+        Ok(self.paths.get("assets")?.join("indexes").join(format!("{}.json", self.inner.meta.asset_index.id)))
+    }
+
+    /// The client's log4j2 configuration and where it's kept, for versions that ship one.
+    fn logging_config(&self) -> Result<Option<(&LoggingConfig, PathBuf)>, PathError> {
+        let Some(config) = self.inner.meta.logging.as_ref().and_then(|logging| logging.client.as_ref()) else { return Ok(None) };
+        Ok(Some((config, self.paths.get("assets")?.join("log_configs").join(&config.file.id))))
+    }
+
+    /// The asset index as a download, so it's fetched and verified with everything else.
+    fn asset_index_download(&self) -> Result<Download, PathError> {
+        let index = &self.inner.meta.asset_index;
+        Ok(Download {
+            url: index.url.clone(),
+            path: self.asset_index_path()?,
+            unzip: false,
+            hash: index.sha1.clone().map(Hash::Sha1),
+            size: index.size,
+            ..Default::default()
+        })
+    }
+
+    /// Reads the asset index from disk, or `None` when it's missing or doesn't match its hash
+    /// and has to be downloaded before the objects it lists are known.
+    fn asset_index(&self) -> Result<Option<Assets>, DownloadError> {
+        let index = self.asset_index_download()?;
+        if !index.hash.as_ref().is_none_or(|hash| hash.digest_file(&index.path).is_ok_and(|found| hash.matches(&found))) {
+            return Ok(None)
+        }
+
+        Ok(std::fs::read(&index.path).ok().and_then(|bytes| serde_json::from_slice(&bytes).ok()))
+    }
+
+    /// The libraries allowed on this platform followed by the client jar, for loaders to extend.
+    pub(crate) fn classpath(&self) -> Result<Classpath, LaunchError> {
+        let meta = &self.inner.meta;
+        let libraries = self.paths.get("libraries")?;
+        let mut classpath = Classpath::default();
+
+        // 按照与下载相同的规则筛选库，并将其构件加入类路径。
+        let env = self.environment();
+        for lib in meta.libraries.iter().filter(|lib| lib.is_allowed(&env)) {
+            if let Some(artifact) = &lib.downloads.artifact {
+                classpath.push(&lib.name, libraries.join(&artifact.path));
+            }
+        }
+
+        // 最后加入 Minecraft 客户端 jar 文件。
+        let client = self.client_coordinate();
+        classpath.push(&client.to_string(), libraries.join(client.path()));
+
+        Ok(classpath)
+    }
+
+    /// The client jar, stored in the libraries like any other artifact.
+    fn client_coordinate(&self) -> MavenCoordinate {
+        MavenCoordinate {
+            group: "com.mojang".to_string(),
+            artifact: "minecraft".to_string(),
+            version: self.inner.meta.id.clone(),
+            classifier: Some("client".to_string()),
+            extension: "jar".to_string(),
+        }
+    }
+
+    /// Natives are extracted per game version, inside the instance's `natives/`.
+    fn natives_dir(&self) -> Result<PathBuf, PathError> {
+        Ok(self.paths.get("natives")?.join(&self.inner.meta.id))
+    }
+
+    /// The natives directory, extracted first if it's missing or empty, as it is for
+    /// instances installed before natives were kept per version or copied without them.
+    fn ensure_natives(&self) -> Result<PathBuf, LaunchError> {
+        let natives_dir = self.natives_dir()?;
+        if !std::fs::read_dir(&natives_dir).is_ok_and(|mut dir| dir.next().is_some()) {
+            log::info!("Extracting natives into {}", natives_dir.display());
+            self.extract_natives().map_err(LaunchError::NativesError)?;
+        }
+
+        Ok(natives_dir)
+    }
+
+    /// The host platform along with the launcher features this instance launches with.
+    fn environment(&self) -> Environment {
+        Environment::current()
+            .with_feature("is_demo_user", false)
+            .with_feature("has_custom_resolution", self.state.resolution.is_some())
+    }
+
+    /// The instance's own asset filter, or the global one.
+    fn asset_filter(&self) -> &AssetFilter {
+        self.state.assets.as_ref().unwrap_or(&self.settings.assets)
+    }
+
+    /// Where `${game_assets}` points: `resources/` for `map_to_resources` indexes and
+    /// `assets/virtual/<index>` for `virtual` ones. Without an index to go by, `resources/`.
+    fn game_assets(&self, assets: Option<&Assets>) -> Result<PathBuf, PathError> {
+        match assets {
+            Some(assets) if !assets.map_to_resources => Ok(self.paths.get("assets")?.join("virtual").join(&self.inner.meta.asset_index.id)),
+            _ => Ok(self.paths.get("resources")?.clone()),
         }
     }
-    Ok(())
 }
 
 impl DownloadSequence for Instance<Vanilla> {
-    fn collect_urls(&mut self) -> Result<Downloads, DownloadError> {
-        let mut dls = Downloads { retries: 5, ..Default::default() };
+    fn resolve(&self) -> Result<Downloads, DownloadError> {
+        let mut dls = Downloads::default();
         let meta = &self.inner.meta;
 
-        let path = self.paths.get("libraries")?
-            .join("com")
-            .join("mojang")
-            .join("minecraft")
-            .join(&self.inner.version.id)
-            .join(format!("minecraft-{}-client.jar", self.inner.version.id));
+        let path = self.paths.get("libraries")?.join(self.client_coordinate().path());
+
+        dls.downloads.push(Download {
+            url: meta.downloads.client.url.clone(),
+            path,
+            unzip: false,
+            hash: Some(Hash::Sha1(meta.downloads.client.sha1.clone())),
+            size: meta.downloads.client.size,
+            ..Default::default()
+        });
 
-        if !path.exists() || !is_file_valid(&path, &meta.downloads.client.sha1)? {
+        let env = self.environment();
+        for lib in meta.libraries.iter().filter(|lib| lib.is_allowed(&env)) {
+            // libraries
+            if let Some(artifact) = &lib.downloads.artifact {
+                dls.downloads.push(Download {
+                    url: artifact.url.clone(),
+                    path: self.paths.get("libraries")?.join(&artifact.path),
+                    unzip: false,
+                    hash: Some(Hash::Sha1(artifact.sha1.clone())),
+                    size: artifact.size,
+                    ..Default::default()
+                });
+            }
+
+            // natives, kept as jars so they can be verified and extracted again
+            if let Some(native) = natives::classifier(lib)? {
+                dls.downloads.push(Download {
+                    url: native.url.clone(),
+                    path: self.paths.get("libraries")?.join(&native.path),
+                    unzip: false,
+                    hash: Some(Hash::Sha1(native.sha1.clone())),
+                    size: native.size,
+                    ..Default::default()
+                });
+            }
+        }
+
+        if let Some((config, path)) = self.logging_config()? {
             dls.downloads.push(Download {
-                url: meta.downloads.client.url.clone(),
+                url: config.file.url.clone(),
                 path,
                 unzip: false,
+                hash: config.file.sha1.clone().map(Hash::Sha1),
+                size: config.file.size,
+                ..Default::default()
             });
         }
 
-        let natives_dir = self.paths.get("natives")?;
+        // assets, kept in the shared object store even when a version wants them by name;
+        // the objects are only known once the index is on disk
+        dls.downloads.push(self.asset_index_download()?);
+        let Some(assets) = self.asset_index()? else { return Ok(dls) };
 
-        let os_type = std::env::consts::OS;
+        let objects_dir = self.paths.get("assets")?.join("objects");
+        let filter = self.asset_filter();
+        for (_, hash) in assets.objects.iter().filter(|(name, _)| filter.allows(name)) {
+            let hash_head = &hash.hash[0..2];
 
-        for lib in &meta.libraries {
-            // libraries
-            if let Some(artifact) = &lib.downloads.artifact {
-                let path = self.paths.get("libraries")?.join(&artifact.path);
-                if !path.exists() || !is_file_valid(&path, &artifact.sha1)? {
-                    dls.downloads.push(Download {
-                        url: artifact.url.clone(),
-                        path,
-                        unzip: false,
-                    });
-                }
-            }
+            dls.downloads.push(Download {
+                url: format!("https://resources.download.minecraft.net/{}/{}", hash_head, hash.hash),
+                path: objects_dir.join(hash_head).join(&hash.hash),
+                unzip: false,
+                hash: Some(Hash::Sha1(hash.hash.clone())),
+                size: Some(hash.size),
+                ..Default::default()
+            });
+        }
+        Ok(dls)
+    }
 
-            match os_type {
-                "windows" => process_natives(lib.natives.as_ref().and_then(|n| n.windows.as_ref()), natives_dir.clone(), lib, &mut dls)?,
-                "linux" => process_natives(lib.natives.as_ref().and_then(|n| n.linux.as_ref()), natives_dir.clone(), lib, &mut dls)?,
-                "macos" => process_natives(lib.natives.as_ref().and_then(|n| n.macos.as_ref()), natives_dir.clone(), lib, &mut dls)?,
-                _ => {} // 或者处理不支持的操作系统类型
-            }
+    fn metadata(&self) -> Result<Vec<PathBuf>, DownloadError> {
+        Ok(vec![meta_path(&self.paths, &self.inner.meta.id)?, self.asset_index_path()?])
+    }
+
+    fn asset_copies(&self) -> Result<Vec<(PathBuf, PathBuf)>, DownloadError> {
+        let Some(assets) = self.asset_index()? else { return Ok(Vec::new()) };
+        if !assets.map_to_resources && !assets.r#virtual {
+            return Ok(Vec::new());
         }
 
-        // assets
-        let asset_id = &meta.asset_index.id;
-        let url = &meta.asset_index.url;
-        let path = self.paths.get("assets")?.join("indexes").join(format!("{}.json", asset_id));
-
-        let assets_str = nizziel::blocking::download(url, &path, false)?;
-        let assets: Assets = serde_json::from_slice(&assets_str)?;
-
-        if asset_id.eq("pre-1.6") || asset_id.eq("legacy") {
-            for (key, hash) in assets.objects {
-                let hash_head = &hash.hash[0..2];
-                let path = self.paths.get("instance")?.join("resources").join(key);
-
-                if !path.exists() && is_file_valid(&path, &hash.hash)? {
-                    dls.downloads.push(Download {
-                        url: format!("https://resources.download.minecraft.net/{}/{}", hash_head, hash.hash),
-                        path,
-                        unzip: false,
-                    });
-                }
-            }
-        } else {
-            let objects_dir = self.paths.get("assets")?.join("objects");
-            for hash in assets.objects.values() {
-                let hash_head = &hash.hash[0..2];
-                let path = objects_dir.join(hash_head).join(&hash.hash);
-
-                if !path.exists() {
-                    dls.downloads.push(Download {
-                        url: format!("https://resources.download.minecraft.net/{}/{}", hash_head, hash.hash),
-                        path,
-                        unzip: false,
-                    });
-                }
-            }
+        let objects_dir = self.paths.get("assets")?.join("objects");
+        let target = self.game_assets(Some(&assets))?;
+
+        let filter = self.asset_filter();
+        Ok(assets.objects.iter()
+            .filter(|(key, _)| filter.allows(key))
+            .map(|(key, hash)| (objects_dir.join(&hash.hash[0..2]).join(&hash.hash), target.join(key)))
+            .collect())
+    }
+
+    fn extract_natives(&self) -> Result<(), DownloadError> {
+        // start over, so natives of a previous version or a bad extraction don't linger
+        let root = self.paths.get("natives")?;
+        if root.exists() {
+            std::fs::remove_dir_all(root)?;
         }
-        Ok(dls)
+
+        let natives_dir = self.natives_dir()?;
+        std::fs::create_dir_all(&natives_dir)?;
+
+        let libraries = self.paths.get("libraries")?;
+        for (lib, jar) in natives::jars(&self.inner.meta.libraries, &self.environment())? {
+            let exclude = lib.extract.as_ref().map(|e| e.exclude.as_slice()).unwrap_or_default();
+            natives::extract(&libraries.join(&jar.path), &natives_dir, exclude)?;
+        }
+
+        Ok(())
     }
 
     fn create_state(&mut self) -> Result<(), DownloadError> {
@@ -160,7 +301,7 @@ impl DownloadSequence for Instance<Vanilla> {
         self.state.components.insert(
             "net.minecraft".to_string(),
             Component::GameComponent {
-                version: self.inner.version.id.to_string()
+                version: self.inner.meta.id.to_string()
             },
         );
 
@@ -178,13 +319,17 @@ impl LaunchSequence for Instance<Vanilla> {
 
         if let Component::GameComponent { version } = self.state.get_component("net.minecraft")? {
             let asset_index = &self.inner.meta.asset_index.id;
-            let game_assets = self.paths.get("resources")?;
+            let assets = std::fs::read(self.asset_index_path()?).ok()
+                .and_then(|bytes| serde_json::from_slice::<Assets>(&bytes).ok());
+            let game_assets = self.game_assets(assets.as_ref())?;
             let assets_path = self.paths.get("assets")?;
 
             let arguments = meta.arguments.get("game").ok_or(LaunchError::ArgumentsNotFound(LaunchArguments::Game))?;
             let account = crate::auth::Accounts::get(self.paths.get("accounts")?)?.get_account(username).unwrap_or(crate::auth::Account::default());
+            let env = self.environment();
+            let (width, height) = self.state.resolution.as_ref().map_or((0, 0), |r| (r.width, r.height));
 
-            return Ok(arguments.iter().map(|x| x
+            return Ok(arguments.iter().flat_map(|arg| arg.values(&env)).map(|x| x
                 .replace("${auth_player_name}", username)
                 .replace("${version_name}", version)
                 .replace("${game_directory}", ".")
@@ -195,8 +340,8 @@ impl LaunchSequence for Instance<Vanilla> {
                 .replace("${user_type}", "mojang")
                 .replace("${version_type}", &meta.r#type)
                 .replace("${user_properties}", "{}")
-                // .replace("${resolution_width}", "1920")
-                // .replace("${resolution_height}", "1080")
+                .replace("${resolution_width}", &width.to_string())
+                .replace("${resolution_height}", &height.to_string())
                 .replace("${game_assets}", game_assets.to_str().unwrap())
                 .replace("${auth_session}", "{}")
             ).collect());
@@ -205,65 +350,17 @@ impl LaunchSequence for Instance<Vanilla> {
         Err(LaunchError::StateError(StateError::ComponentNotFound(String::from("net.minecraft"))))
     }
 
-    // 定义 `get_classpath` 函数，返回一个包含 String 或 LaunchError 的 Result。
     fn get_classpath(&self) -> Result<String, LaunchError> {
-        // 从对象的内部状态访问元数据和库路径。
-        let meta = &self.inner.meta;
-        let libraries = self.paths.get("libraries")?;
-
-        // 初始化一个 String 类型的 classpath 变量，预分配足够的容量。
-        let mut classpath = String::with_capacity(
-            (libraries.to_str().unwrap().len() * meta.libraries.len())
-                + (meta.libraries.len() * 2)
-                + meta.libraries.iter().map(|lib| lib.downloads.artifact.as_ref().map_or(0, |a| a.path.len())).sum::<usize>()
-        );
-
-        // 获取当前操作系统类型。
-        let os_type = std::env::consts::OS;
-
-        // 遍历元数据中的所有库。
-        'outer: for lib in &meta.libraries {
-            // 如果库有规则，则检查这些规则。
-            if let Some(rules) = &lib.rules {
-                for rule in rules {
-                    if let Some(os) = &rule.os {
-                        if let Some(name) = &os.name {
-                            // 检查规则与当前操作系统是否匹配。
-                            let os_name = if name == "osx" { "macos" } else { name };
-                            if rule.action.eq("allow") && os_name.ne(os_type) ||
-                                rule.action.eq("disallow") && os_name.eq(os_type) {
-                                continue 'outer;
-                            }
-                        }
-                    }
-                }
-            }
-
-            // 如果库有下载的构件信息，将其路径添加到类路径中。
-            if let Some(artifact) = &lib.downloads.artifact {
-                classpath.push_str(libraries.to_str().unwrap());
-                classpath.push('/');
-                classpath.push_str(&artifact.path);
-                classpath.push(';');
-            }
-        }
-
-        // 构建 Minecraft 客户端 jar 文件的名称和路径，并将其添加到类路径中。
-        let jar_name = format!("minecraft-{}-client.jar", meta.id);
-        let jar_path = libraries.join("com").join("mojang").join("minecraft").join(meta.id.clone()).join(jar_name);
-        classpath.push_str(jar_path.to_str().unwrap());
-
-        // 返回构建好的类路径。
-        Ok(classpath)
+        Ok(self.classpath()?.build())
     }
 
-
     fn get_jvm_arguments(&self, classpath: &str) -> Result<Vec<String>, LaunchError> {
-        let natives_directory = self.paths.get("natives")?;
+        let natives_directory = self.ensure_natives()?;
 
         let mut jvm_arguments = {
             if let Some(arguments) = &self.inner.meta.arguments.get("jvm") {
-                arguments.iter().map(|x| x
+                let env = self.environment();
+                arguments.iter().flat_map(|arg| arg.values(&env)).map(|x| x
                     .replace("${natives_directory}", natives_directory.to_str().unwrap())
                     .replace("${launcher_name}", "rimca")
                     .replace("${launcher_version}", "3.0")
@@ -278,6 +375,10 @@ impl LaunchSequence for Instance<Vanilla> {
             }
         };
 
+        if let Some((config, path)) = self.logging_config()? {
+            jvm_arguments.push(config.argument.replace("${path}", &path.to_string_lossy()));
+        }
+
         if let Ok(Component::JavaComponent { arguments, .. }) = &self.state.get_component("java") {
             if let Some(args) = arguments {
                 jvm_arguments.extend(args.split_whitespace().map(|s| s.to_string()));
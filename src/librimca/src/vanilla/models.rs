@@ -3,12 +3,19 @@ use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize)]
 pub struct Assets {
+    /// Pre-1.6 indexes: objects are read from the instance's `resources/` under their names.
+    #[serde(default)]
+    pub map_to_resources: bool,
+    /// Legacy indexes: objects are read from `assets/virtual/<index>/` under their names.
+    #[serde(default, rename = "virtual")]
+    pub r#virtual: bool,
     pub objects: HashMap<String, Hash>
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Hash {
     pub hash: String,
+    pub size: u64,
 }
 
 //----------------
@@ -17,16 +24,31 @@ pub struct Hash {
 #[serde(rename_all = "camelCase", rename = "Version")]
 pub struct Meta {
     #[serde(deserialize_with = "arguments_deserialiser", alias = "minecraftArguments")]
-    pub arguments: HashMap<String, Vec<String>>,    
+    pub arguments: HashMap<String, Vec<Argument>>,    
     pub asset_index: File,
     pub assets: String,
     pub downloads: Downloads,
     pub id: String,
     pub libraries: Vec<Library>,
+    pub logging: Option<Logging>,
     pub main_class: String,
     pub r#type: String
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct Logging {
+    pub client: Option<LoggingConfig>,
+}
+
+/// A log4j2 configuration the game is pointed at through `argument`.
+#[derive(Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Carries a `${path}` placeholder for where the file was stored.
+    pub argument: String,
+    pub file: File,
+    pub r#type: String,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Downloads {
     pub client: Download,
@@ -36,13 +58,16 @@ pub struct Downloads {
 #[derive(Serialize, Deserialize)]
 pub struct Download {
     pub url: String,
-    pub sha1: String
+    pub sha1: String,
+    pub size: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct File {
     pub id: String,
-    pub url: String
+    pub url: String,
+    pub sha1: Option<String>,
+    pub size: Option<u64>,
 }
 
 //-----------------
@@ -53,6 +78,14 @@ pub struct Library {
     pub downloads: LibraryDownload,
     pub natives: Option<Natives>,
     pub rules: Option<Vec<Rule>>, 
+    pub extract: Option<Extract>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Extract {
+    /// Entry prefixes to leave out when unpacking natives, usually `META-INF/`.
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -65,26 +98,39 @@ pub struct LibraryDownload {
 pub struct Artifact {
     pub url: String,
     pub path: String,
-    pub sha1: String
+    pub sha1: String,
+    pub size: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Natives {
     pub linux: Option<String>,
+    #[serde(rename = "osx")]
     pub macos: Option<String>,
     pub windows: Option<String>
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Rule {
-    pub action: String,
+    pub action: Action,
     pub os: Option<Os>,
+    /// Launcher features that all have to be in the given state for the rule to apply.
+    pub features: Option<HashMap<String, bool>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Allow,
+    Disallow,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Os {
     pub name: Option<String>,
-    // pub arch: Option<String>,
+    pub arch: Option<String>,
+    /// A regular expression over the operating system version.
+    pub version: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -93,32 +139,38 @@ pub struct Native {
     pub natives_linux: Option<Artifact>,
 }
 
-pub fn arguments_deserialiser<'de, D: Deserializer<'de>>(d: D) -> Result<HashMap<String, Vec<String>>, D::Error> {
-    let value: serde_json::Value = serde::Deserialize::deserialize(d)?;
-
-    if let Some(value) = value.as_str() {
-        let mut map = HashMap::with_capacity(value.len());
-        let arguments: Vec<String> = value.split_whitespace().map(|s| s.to_string()).collect();
-
-        map.insert("game".to_string(), arguments);
-        return Ok(map)
-
-    } else if let Some(value) = value.as_object() {
-        let map = value.into_iter()
-             .map(|(key, vec)| (
-                key.to_string(), 
-                vec
-                    .as_array()
-                    .unwrap()
-                    .iter()
-                    .filter_map(|value| {
-                        value.as_str().map(|x| x.to_string())
-                    }).collect()
-                )
-             ).collect();
-        
-        return Ok(map)
-    }
-
-      panic!()
-}
\ No newline at end of file
+/// A game or JVM argument, either always passed or only when its rules allow it.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Argument {
+    Plain(String),
+    Conditional {
+        rules: Vec<Rule>,
+        value: ArgumentValue,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ArgumentValue {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawArguments {
+    /// `minecraftArguments` from before 1.13, one string of game arguments.
+    Legacy(String),
+    Modern(HashMap<String, Vec<Argument>>),
+}
+
+pub fn arguments_deserialiser<'de, D: Deserializer<'de>>(d: D) -> Result<HashMap<String, Vec<Argument>>, D::Error> {
+    Ok(match RawArguments::deserialize(d)? {
+        RawArguments::Legacy(value) => {
+            let arguments = value.split_whitespace().map(|s| Argument::Plain(s.to_string())).collect();
+            HashMap::from([("game".to_string(), arguments)])
+        },
+        RawArguments::Modern(map) => map,
+    })
+}
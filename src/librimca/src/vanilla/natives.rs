@@ -0,0 +1,89 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use zip::ZipArchive;
+use crate::error::DownloadError;
+use crate::maven::MavenCoordinate;
+use super::models::{ Artifact, Library };
+use super::rules::Environment;
+
+/// File endings of native libraries the JVM on this platform can load.
+const EXTENSIONS: &[&str] = if cfg!(target_os = "windows") {
+    &[".dll"]
+} else if cfg!(target_os = "macos") {
+    &[".dylib", ".jnilib"]
+} else {
+    &[".so"]
+};
+
+/// Every jar of `libraries` holding natives for this platform.
+///
+/// Before 1.19 natives hang off a library as a classifier named in its `natives` map,
+/// possibly with an `${arch}` placeholder. Later versions ship them as libraries of their
+/// own, classified `natives-<platform>` and gated by rules.
+pub(crate) fn jars<'a>(libraries: &'a [Library], env: &Environment) -> Result<Vec<(&'a Library, &'a Artifact)>, DownloadError> {
+    let mut jars = Vec::new();
+
+    for lib in libraries.iter().filter(|lib| lib.is_allowed(env)) {
+        if let Some(artifact) = classifier(lib)? {
+            jars.push((lib, artifact));
+        } else if is_native_library(lib, env) {
+            if let Some(artifact) = &lib.downloads.artifact {
+                jars.push((lib, artifact));
+            }
+        }
+    }
+
+    Ok(jars)
+}
+
+/// The classifier jar holding this platform's natives for `lib`, if it has any.
+pub(crate) fn classifier(lib: &Library) -> Result<Option<&Artifact>, DownloadError> {
+    let natives = lib.natives.as_ref();
+    let key = match std::env::consts::OS {
+        "windows" => natives.and_then(|n| n.windows.as_ref()),
+        "linux" => natives.and_then(|n| n.linux.as_ref()),
+        "macos" => natives.and_then(|n| n.macos.as_ref()),
+        _ => None // 或者处理不支持的操作系统类型
+    };
+
+    let Some(key) = key else { return Ok(None) };
+    let key = key.replace("${arch}", if cfg!(target_pointer_width = "64") { "64" } else { "32" });
+
+    Ok(lib.downloads.classifiers.as_ref()
+        .ok_or_else(|| DownloadError::LibraryNoClassifiers(lib.name.clone()))?
+        .get(&key))
+}
+
+/// Whether `lib` is classified `natives-<platform>[-<arch>]`, for this architecture if it names one.
+fn is_native_library(lib: &Library, env: &Environment) -> bool {
+    lib.name.parse::<MavenCoordinate>().ok()
+        .and_then(|coordinate| coordinate.classifier)
+        .and_then(|classifier| classifier.strip_prefix("natives-").map(String::from))
+        .is_some_and(|platform| platform.split_once('-').is_none_or(|(_, arch)| arch == env.os_arch))
+}
+
+/// Unpacks the native libraries in `jar` straight into `dir`, skipping entries under
+/// any of the `exclude` prefixes and anything the platform can't load.
+pub(crate) fn extract(jar: &Path, dir: &Path, exclude: &[String]) -> Result<(), DownloadError> {
+    let mut zip = ZipArchive::new(BufReader::new(File::open(jar)?))?;
+
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let name = entry.name().to_string();
+
+        if entry.is_dir() || exclude.iter().any(|prefix| name.starts_with(prefix.as_str())) {
+            continue;
+        }
+
+        if !EXTENSIONS.iter().any(|ext| name.ends_with(ext)) {
+            continue;
+        }
+
+        // lwjgl 3 jars nest libraries by platform, the library path wants them side by side
+        let Some(file_name) = Path::new(&name).file_name() else { continue };
+        std::io::copy(&mut entry, &mut File::create(dir.join(file_name))?)?;
+    }
+
+    Ok(())
+}
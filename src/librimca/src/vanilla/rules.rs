@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use regex::Regex;
+use super::models::{ Action, Argument, ArgumentValue, Library, Rule };
+
+/// What rules in a version meta are evaluated against: the platform, in the names
+/// Mojang uses, and the launcher features in play.
+#[derive(Clone, Debug, Default)]
+pub struct Environment {
+    pub os_name: String,
+    pub os_arch: String,
+    pub os_version: String,
+    pub features: HashMap<String, bool>,
+}
+
+impl Environment {
+    /// The host this process runs on, with no features enabled.
+    pub fn current() -> Self {
+        let os_name = match std::env::consts::OS {
+            "macos" => "osx",
+            os => os,
+        };
+
+        let os_arch = match std::env::consts::ARCH {
+            "aarch64" => "arm64",
+            "arm" => "arm32",
+            arch => arch,
+        };
+
+        Self {
+            os_name: os_name.to_string(),
+            os_arch: os_arch.to_string(),
+            os_version: os_version().unwrap_or_default(),
+            features: HashMap::new(),
+        }
+    }
+
+    pub fn with_feature(mut self, name: &str, enabled: bool) -> Self {
+        self.features.insert(name.to_string(), enabled);
+        self
+    }
+}
+
+impl Rule {
+    /// Whether every condition of the rule holds, regardless of its action.
+    pub fn matches(&self, env: &Environment) -> bool {
+        if let Some(os) = &self.os {
+            if os.name.as_ref().is_some_and(|name| name != &env.os_name) {
+                return false
+            }
+
+            if os.arch.as_ref().is_some_and(|arch| arch != &env.os_arch) {
+                return false
+            }
+
+            if let Some(version) = &os.version {
+                if !Regex::new(version).is_ok_and(|re| re.is_match(&env.os_version)) {
+                    return false
+                }
+            }
+        }
+
+        self.features.iter().flatten()
+            .all(|(feature, wanted)| env.features.get(feature).copied().unwrap_or(false) == *wanted)
+    }
+}
+
+/// Whether `rules` let something through: nothing is allowed unless a rule says so,
+/// and the last matching rule wins. Without any rules everything is allowed.
+pub fn allowed(rules: &[Rule], env: &Environment) -> bool {
+    if rules.is_empty() {
+        return true
+    }
+
+    rules.iter()
+        .rev()
+        .find(|rule| rule.matches(env))
+        .is_some_and(|rule| rule.action == Action::Allow)
+}
+
+impl Library {
+    /// Whether the library's rules let it onto this platform, for download, natives and classpath alike.
+    pub fn is_allowed(&self, env: &Environment) -> bool {
+        allowed(self.rules.as_deref().unwrap_or_default(), env)
+    }
+}
+
+impl Argument {
+    /// The values this argument contributes under `env`.
+    pub fn values(&self, env: &Environment) -> Vec<&str> {
+        match self {
+            Self::Plain(value) => vec![value],
+            Self::Conditional { rules, value } if allowed(rules, env) => match value {
+                ArgumentValue::One(value) => vec![value],
+                ArgumentValue::Many(values) => values.iter().map(String::as_str).collect(),
+            },
+            Self::Conditional { .. } => Vec::new(),
+        }
+    }
+}
+
+/// The operating system version as the JVM reports it in `os.version`, best effort.
+fn os_version() -> Option<String> {
+    let output = |program: &str, args: &[&str]| std::process::Command::new(program).args(args).output().ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+
+    match std::env::consts::OS {
+        "linux" => std::fs::read_to_string("/proc/sys/kernel/osrelease").ok().map(|s| s.trim().to_string()),
+        "macos" => output("sw_vers", &["-productVersion"]),
+        // `Microsoft Windows [Version 10.0.19045.3803]`
+        "windows" => output("cmd", &["/C", "ver"])
+            .and_then(|ver| ver.rsplit(' ').next().map(|v| v.trim_end_matches(']').to_string())),
+        _ => output("uname", &["-r"]),
+    }
+}
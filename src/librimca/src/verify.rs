@@ -1,33 +1,189 @@
-use sha1::{ Sha1, Digest };
-use std::path::Path;
+use serde::{ Serialize, Deserialize };
+use std::collections::HashMap;
+use std::path::{ Path, PathBuf };
+use std::sync::Mutex;
+use std::sync::atomic::{ AtomicBool, AtomicUsize, Ordering };
+use std::time::UNIX_EPOCH;
+use nizziel::{ Download, Downloads, Hash };
+use crate::log4j::{ self, Vulnerability };
 
-pub fn is_file_valid(path: &Path, sha1: &str) -> std::io::Result<bool> {
-    let bytes = std::fs::read(path)?;
-    let hash = Sha1::digest(&bytes);
+/// What a verification pass found, before any repair.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub checked: usize,
+    pub missing: Vec<PathBuf>,
+    pub corrupt: Vec<PathBuf>,
+    /// A log4j-core among the libraries open to Log4Shell, mitigated whenever the instance launches.
+    pub log4j: Option<Vulnerability>,
+}
 
-    if parse_hex_pairs(sha1).eq(hash.into_iter()) {     
-        return Ok(true)
+impl Report {
+    pub fn is_intact(&self) -> bool {
+        self.missing.is_empty() && self.corrupt.is_empty()
     }
+}
+
+/// Sorts `dls` into a report and the downloads needed to put the broken files right.
+pub(crate) fn check(dls: Downloads, index: &HashIndex) -> (Report, Downloads) {
+    let mut report = Report::default();
+    let mut broken = Downloads::default();
+    let present = downloaded(&dls.downloads, index);
+    report.log4j = log4j::scan(dls.downloads.iter().map(|dl| dl.path.as_path()));
+
+    for (download, present) in dls.downloads.into_iter().zip(present) {
+        report.checked += 1;
+        if download.unzip || present {
+            continue;
+        }
+
+        if download.path.exists() {
+            report.corrupt.push(download.path.clone());
+        } else {
+            report.missing.push(download.path.clone());
+        }
+        broken.downloads.push(download);
+    }
+
+    (report, broken)
+}
+
+/// Whether each of `downloads` is already on disk with the expected size and hash.
+///
+/// Files are hashed on every core at once, and a file unchanged since `index` last saw it
+/// isn't read at all unless `index` was opened with [`HashIndex::fresh`].
+pub(crate) fn downloaded(downloads: &[Download], index: &HashIndex) -> Vec<bool> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get()).min(downloads.len().max(1));
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![false; downloads.len()]);
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(download) = downloads.get(i) else { break };
 
-    Ok(false)
+                let present = is_downloaded(download, index);
+                results.lock().unwrap_or_else(|e| e.into_inner())[i] = present;
+            });
+        }
+    });
+
+    results.into_inner().unwrap_or_else(|e| e.into_inner())
 }
 
-fn parse_digest_digit(b: u8) -> Option<u8> {
-    match b {
-        b'0'..=b'9' => Some(b - b'0'),
-        b'a'..=b'f' => Some(b - b'a' + 10),
-        _ => None,
+pub(crate) fn is_downloaded(download: &Download, index: &HashIndex) -> bool {
+    let Ok(metadata) = std::fs::metadata(&download.path) else { return false };
+    if !metadata.is_file() || download.size.is_some_and(|size| size != metadata.len()) {
+        return false
+    }
+
+    match &download.hash {
+        Some(hash) => index.digest(&download.path, hash).is_ok_and(|found| hash.matches(&found)),
+        None => true,
     }
 }
 
-fn parse_hex_pairs(s: &str) -> impl Iterator<Item = u8> + '_ {
-    let mut bytes = s.as_bytes();
-    core::iter::from_fn(move || {
-        if let [b1, b2, rest @ ..] = bytes {
-            bytes = rest;
-            Some(parse_digest_digit(*b1)? * 16 + parse_digest_digit(*b2)?)
-        } else {
-            None
+#[derive(Serialize, Deserialize, Clone)]
+struct Entry {
+    size: u64,
+    modified: u64,
+    /// Digests by algorithm, hex encoded.
+    digests: HashMap<String, String>,
+}
+
+/// Digests of files under the base directory, kept in `hashes.json` and trusted for as
+/// long as a file's size and modification time stay the same.
+pub(crate) struct HashIndex {
+    path: PathBuf,
+    entries: Mutex<HashMap<PathBuf, Entry>>,
+    changed: AtomicBool,
+    /// Whether digests of unchanged files are taken from the index instead of the files.
+    reuse: bool,
+}
+
+impl HashIndex {
+    /// Loads the index at `path`, starting empty when it's missing or unreadable.
+    pub(crate) fn open(path: &Path) -> Self {
+        let entries = std::fs::read(path).ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        Self {
+            path: path.to_path_buf(),
+            entries: Mutex::new(entries),
+            changed: AtomicBool::new(false),
+            reuse: true,
         }
-    })
-}
\ No newline at end of file
+    }
+
+    /// Loads the index at `path` but hashes every file again, for when a file may have
+    /// changed without its size or modification time doing so. The digests found are still recorded.
+    pub(crate) fn fresh(path: &Path) -> Self {
+        Self { reuse: false, ..Self::open(path) }
+    }
+
+    /// The digest of `path` in the algorithm of `hash`, reused when the file is unchanged.
+    pub(crate) fn digest(&self, path: &Path, hash: &Hash) -> std::io::Result<String> {
+        let (size, modified) = stamp(path)?;
+
+        if let Some(entry) = self.lock().get(path).filter(|_| self.reuse) {
+            if entry.size == size && entry.modified == modified {
+                if let Some(found) = entry.digests.get(hash.algorithm()) {
+                    return Ok(found.clone())
+                }
+            }
+        }
+
+        let found = hash.digest_file(path)?;
+        self.insert(path, size, modified, hash.algorithm(), &found);
+        Ok(found)
+    }
+
+    /// Records `path` as holding `hash`, for files whose content was just checked elsewhere.
+    pub(crate) fn record(&self, path: &Path, hash: &Hash) {
+        if let Ok((size, modified)) = stamp(path) {
+            self.insert(path, size, modified, hash.algorithm(), hash.expected());
+        }
+    }
+
+    /// Writes the index back if anything was hashed, dropping files that no longer exist.
+    pub(crate) fn save(&self) -> std::io::Result<()> {
+        if !self.changed.load(Ordering::Relaxed) {
+            return Ok(())
+        }
+
+        let mut entries = self.lock();
+        entries.retain(|path, _| path.is_file());
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_vec(&*entries)?)
+    }
+
+    fn insert(&self, path: &Path, size: u64, modified: u64, algorithm: &str, digest: &str) {
+        let mut entries = self.lock();
+        let entry = entries.entry(path.to_path_buf()).or_insert_with(|| Entry { size, modified, digests: HashMap::new() });
+
+        if entry.size != size || entry.modified != modified {
+            *entry = Entry { size, modified, digests: HashMap::new() };
+        }
+
+        entry.digests.insert(algorithm.to_string(), digest.to_lowercase());
+        self.changed.store(true, Ordering::Relaxed);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Entry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Size and modification time in nanoseconds, which together stand in for the content.
+fn stamp(path: &Path) -> std::io::Result<(u64, u64)> {
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata.modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+
+    Ok((metadata.len(), modified))
+}
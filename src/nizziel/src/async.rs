@@ -1,70 +1,264 @@
-use std::io::{ Seek, SeekFrom, Write };
-use std::path::{ Path };
-use std::fs::create_dir_all;
+use std::io::Write;
+use std::collections::HashSet;
+use std::path::{ Path, PathBuf };
+use std::fs::{ create_dir_all, OpenOptions };
+use std::time::Duration;
 use futures::StreamExt;
-use crate::{ Error, Downloads, create_file };
+use reqwest::StatusCode;
+use reqwest::header::{ RANGE, RETRY_AFTER };
+use crate::hash::Hasher;
+use crate::report::Outcome;
+use crate::progress::Events;
+use crate::policy::{ self, Throttle };
+use crate::{ Error, Download, Downloads, DownloadPolicy, Report, Failure, Progress };
 
-pub async fn download(dls: Downloads) -> Result<(), Error> {
-    let client = reqwest::Client::new();
-    let retries = dls.retries.clone();
+/// State shared by every request of one batch.
+struct Session {
+    client: reqwest::Client,
+    policy: DownloadPolicy,
+    events: Events,
+    throttle: Option<Throttle>,
+}
+
+pub async fn download(mut dls: Downloads) -> Result<Report, Error> {
+    dedupe(&mut dls.downloads);
 
-    let fetches = futures::stream::iter(    
-        dls.downloads.iter().map(|download| {
-            let client = client.clone();
+    let session = Session {
+        client: reqwest::Client::new(),
+        throttle: dls.policy.bytes_per_second.map(Throttle::new),
+        events: Events(dls.progress),
+        policy: dls.policy,
+    };
+    let session = &session;
 
+    session.events.send(|| Progress::Planned {
+        files: dls.downloads.len(),
+        bytes: dls.downloads.iter().filter_map(|d| d.size).sum(),
+    });
+
+    let fetches = futures::stream::iter(
+        dls.downloads.into_iter().map(|download| {
             async move {
-                if download.unzip {
-                    create_dir_all(&download.path).unwrap();
-
-                    for _ in 1..=retries {
-                        if reqwest::StatusCode::from_u16({ 
-                            if let Ok(response) = client.get(&download.url).send().await {
-                                let status = response.status().as_u16();
-                                unzip(&response.bytes().await.unwrap().to_vec(), &download.path).await;
-                                status
-                            } else {
-                                reqwest::StatusCode::BAD_REQUEST.as_u16()
-                            }
-                        }).unwrap_or(reqwest::StatusCode::BAD_REQUEST).is_success() {
-                            break;
-                        }
+                if !download.unzip && is_present(&download) {
+                    session.events.send(|| Progress::Skipped { path: download.path.clone(), size: download.size });
+                    return (download, Ok(Outcome::Skipped))
+                }
+
+                session.events.send(|| Progress::Started { path: download.path.clone(), size: download.size });
+                let mut result = fetch(session, &download).await;
+                let mut attempt = 1;
+
+                while let Err(err) = &result {
+                    if attempt >= session.policy.retries || !session.policy.is_retryable(err) {
+                        break;
                     }
-                } else {
-                    let file = create_file(&download.path, true, true).unwrap();
-                    let mut writer = std::io::BufWriter::new(file);
-
-                    for _ in 1..=retries {
-                        if reqwest::StatusCode::from_u16({ 
-                            if let Ok(mut response) = client.get(&download.url).send().await {
-                                let status = response.status().as_u16();
-                                let mut current: u64 = 0;
-
-                                writer.seek(SeekFrom::Start(current)).unwrap_or(0);
-                                while let Some(bytes) = response.chunk().await.unwrap_or(None) {
-                                    writer.write_all(&bytes).unwrap();
-                                    current += bytes.len() as u64;
-                                }
-
-                                status
-                            } else {
-                                reqwest::StatusCode::BAD_REQUEST.as_u16()
-                            }
-                        }).unwrap_or(reqwest::StatusCode::BAD_REQUEST).is_success() {
-                            break;
-                        }
-                    }    
+
+                    session.events.send(|| Progress::Retry { path: download.path.clone(), attempt, reason: err.to_string() });
+                    tokio::time::sleep(session.policy.delay(attempt, err)).await;
+
+                    result = fetch(session, &download).await;
+                    attempt += 1;
                 }
+
+                session.events.send(|| Progress::Finished { path: download.path.clone(), success: result.is_ok() });
+                (download, result)
             }
         })
-    ).buffer_unordered(100).collect::<Vec<()>>();
+    ).buffer_unordered(session.policy.max_concurrent.max(1)).collect::<Vec<(Download, Result<Outcome, Error>)>>();
+
+    let mut report = Report::default();
+    for (download, result) in fetches.await {
+        match result {
+            Ok(Outcome::Succeeded(bytes)) => {
+                report.bytes += bytes;
+                report.succeeded.push(download.path);
+            },
+            Ok(Outcome::Skipped) => report.skipped.push(download.path),
+            Err(reason) => report.failed.push(Failure { download, reason }),
+        }
+    }
+
+    Ok(report)
+}
+
+async fn fetch(session: &Session, download: &Download) -> Result<Outcome, Error> {
+    if download.unzip {
+        fetch_unzip(session, download).await.map(Outcome::Succeeded)
+    } else {
+        fetch_file(session, download).await.map(Outcome::Succeeded)
+    }
+}
+
+/// Drops every download writing to a path an earlier one already writes to, since both would race
+/// on one `.part` sidecar. Archives are only duplicates of the same url, as several unzip into one directory.
+fn dedupe(downloads: &mut Vec<Download>) {
+    let mut seen = HashSet::new();
+    downloads.retain(|download| {
+        seen.insert((download.path.clone(), download.unzip.then(|| download.url.clone())))
+    });
+}
+
+/// Whether the destination already holds the expected file; only provable with a hash.
+fn is_present(download: &Download) -> bool {
+    match (&download.hash, download.path.metadata()) {
+        (Some(hash), Ok(metadata)) => download.size.is_none_or(|size| size == metadata.len())
+            && hash.digest_file(&download.path).is_ok_and(|found| hash.matches(&found)),
+        _ => false
+    }
+}
+
+async fn fetch_unzip(session: &Session, download: &Download) -> Result<u64, Error> {
+    create_dir_all(&download.path)?;
+
+    // archives are extracted into `path`, so the sidecar lives inside it under the remote file name
+    let name = download.url.rsplit('/').next().unwrap_or("archive");
+    let part = download.path.join(format!("{}.part", name));
+
+    let bytes = fetch_part(session, download, &part).await?;
+    let extracted = unzip(&part, &download.path).await;
+    std::fs::remove_file(&part)?;
+    extracted.map(|_| bytes)
+}
+
+async fn fetch_file(session: &Session, download: &Download) -> Result<u64, Error> {
+    let part = part_path(&download.path);
+
+    let bytes = fetch_part(session, download, &part).await?;
+    std::fs::rename(&part, &download.path)?;
+    Ok(bytes)
+}
+
+/// Downloads into `part`, resuming from its current length when the server honours `Range`.
+/// The sidecar is only left behind on a transfer error, so a corrupt one never gets resumed.
+/// Returns the number of bytes received.
+async fn fetch_part(session: &Session, download: &Download, part: &Path) -> Result<u64, Error> {
+    if let Some(parent) = part.parent() {
+        create_dir_all(parent)?;
+    }
+
+    let mut offset = part.metadata().map(|m| m.len()).unwrap_or(0);
+
+    if offset > 0 && download.size.is_some_and(|size| offset >= size) {
+        // a previous attempt got every byte but failed before renaming
+        if verify_part(download, part, offset).is_ok() {
+            session.events.send(|| Progress::Resumed { path: download.path.clone(), offset });
+            return Ok(0)
+        }
+        std::fs::remove_file(part)?;
+        offset = 0;
+    }
+
+    let mut response = open(session, download, offset).await?;
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        std::fs::remove_file(part)?;
+        response = open(session, download, 0).await?;
+        offset = 0;
+    }
+
+    let mut hasher = download.hash.as_ref().map(Hasher::new);
+    let resumed = offset > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+    let file = if resumed {
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update_file(part)?;
+        }
+        session.events.send(|| Progress::Resumed { path: download.path.clone(), offset });
+        OpenOptions::new().append(true).open(part)?
+    } else {
+        offset = 0;
+        OpenOptions::new().create(true).write(true).truncate(true).open(part)?
+    };
+
+    let mut writer = std::io::BufWriter::new(file);
+    let mut current = offset;
+
+    while let Some(bytes) = response.chunk().await? {
+        if let Some(throttle) = &session.throttle {
+            throttle.consume(bytes.len() as u64).await;
+        }
+        writer.write_all(&bytes)?;
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(&bytes);
+        }
+        session.events.send(|| Progress::Received { path: download.path.clone(), bytes: bytes.len() as u64 });
+        current += bytes.len() as u64;
+    }
+    writer.flush()?;
+
+    verify(download, hasher, current).inspect_err(|_| {
+        let _ = std::fs::remove_file(part);
+    })?;
+
+    Ok(current - offset)
+}
+
+/// Asks `url` and then each fallback in turn, settling on the first host that answers successfully.
+async fn open(session: &Session, download: &Download, offset: u64) -> Result<reqwest::Response, Error> {
+    let mut last = None;
+
+    for url in std::iter::once(&download.url).chain(&download.fallbacks) {
+        last = Some(match request(session, url, offset).send().await {
+            Ok(response) if response.status().is_success() || response.status() == StatusCode::RANGE_NOT_SATISFIABLE => {
+                return Ok(response)
+            },
+            Ok(response) => {
+                let retry_after = response.headers().get(RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(policy::retry_after);
+                Error::Status { url: url.clone(), status: response.status().as_u16(), retry_after }
+            },
+            Err(err) => err.into(),
+        });
+    }
+
+    Err(last.unwrap_or_else(|| Error::Status { url: download.url.clone(), status: 0, retry_after: None }))
+}
+
+fn request(session: &Session, url: &str, offset: u64) -> reqwest::RequestBuilder {
+    let mut request = session.client.get(url);
+    if let Some(secs) = session.policy.timeout_secs {
+        request = request.timeout(Duration::from_secs(secs));
+    }
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    request
+}
+
+fn verify_part(download: &Download, part: &Path, size: u64) -> Result<(), Error> {
+    let mut hasher = download.hash.as_ref().map(Hasher::new);
+    if let Some(hasher) = hasher.as_mut() {
+        hasher.update_file(part)?;
+    }
+
+    verify(download, hasher, size)
+}
+
+fn verify(download: &Download, hasher: Option<Hasher>, size: u64) -> Result<(), Error> {
+    if let Some(expected) = download.size {
+        if expected != size {
+            return Err(Error::SizeMismatch { path: download.path.clone(), expected, found: size })
+        }
+    }
+
+    if let (Some(hash), Some(hasher)) = (&download.hash, hasher) {
+        let found = hasher.finalize();
+        if !hash.matches(&found) {
+            return Err(Error::HashMismatch { path: download.path.clone(), expected: hash.expected().to_string(), found })
+        }
+    }
 
-    fetches.await;
     Ok(())
 }
 
-pub async fn unzip(bytes: &[u8], path: &Path) {
-    let reader = std::io::Cursor::new(bytes);
-    let mut zip = zip::ZipArchive::new(reader).unwrap();
-    let path = format!("{}/", path.to_str().unwrap());
-    zip.extract(path).unwrap();
-}
\ No newline at end of file
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+pub async fn unzip(archive: &Path, path: &Path) -> Result<(), Error> {
+    let reader = std::fs::File::open(archive)?;
+    let mut zip = zip::ZipArchive::new(reader)?;
+    zip.extract(path)?;
+    Ok(())
+}
@@ -3,9 +3,10 @@ use std::path::Path;
 use std::io::Write;
 
 pub fn download(url: &str, path: &Path, dezip: bool) -> Result<Vec<u8>, Error> {
-    let mut file = create_file(&path, true, true)?;
-    let resp = reqwest::blocking::get(url)?;
+    let resp = reqwest::blocking::get(url)?.error_for_status()?;
     let bytes = resp.bytes()?;
+    let mut file = create_file(path, true, true)?;
+    file.set_len(0)?;
     file.write_all(&bytes)?;
 
     if dezip {
@@ -1,4 +1,5 @@
 use thiserror::Error;
+use std::path::PathBuf;
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -8,4 +9,10 @@ pub enum Error {
     ZipError(#[from] zip::result::ZipError),
     #[error("reqwest error: {0}")]
     ReqwestError(#[from] reqwest::Error),
+    #[error("`{url}` responded with status {status}")]
+    Status { url: String, status: u16, retry_after: Option<u64> },
+    #[error("hash mismatch for `{path}`: expected {expected}, found {found}")]
+    HashMismatch { path: PathBuf, expected: String, found: String },
+    #[error("size mismatch for `{path}`: expected {expected} bytes, found {found}")]
+    SizeMismatch { path: PathBuf, expected: u64, found: u64 },
 }
\ No newline at end of file
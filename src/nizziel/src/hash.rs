@@ -0,0 +1,87 @@
+use sha1::Sha1;
+use sha2::{ Digest, Sha256, Sha512 };
+use std::io::Read;
+use std::path::Path;
+
+/// Expected digest of a download, as a lowercase hex string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Hash {
+    Sha1(String),
+    Sha256(String),
+    Sha512(String),
+}
+
+impl Hash {
+    pub fn expected(&self) -> &str {
+        match self {
+            Self::Sha1(hex) | Self::Sha256(hex) | Self::Sha512(hex) => hex,
+        }
+    }
+
+    /// Name of the digest algorithm, such as `sha1`.
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            Self::Sha1(_) => "sha1",
+            Self::Sha256(_) => "sha256",
+            Self::Sha512(_) => "sha512",
+        }
+    }
+
+    pub fn matches(&self, found: &str) -> bool {
+        self.expected().eq_ignore_ascii_case(found)
+    }
+
+    /// Streams the file at `path` through the matching digest.
+    pub fn digest_file(&self, path: &Path) -> std::io::Result<String> {
+        let mut hasher = Hasher::new(self);
+        hasher.update_file(path)?;
+        Ok(hasher.finalize())
+    }
+}
+
+pub(crate) enum Hasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    pub(crate) fn new(hash: &Hash) -> Self {
+        match hash {
+            Hash::Sha1(_) => Self::Sha1(Sha1::new()),
+            Hash::Sha256(_) => Self::Sha256(Sha256::new()),
+            Hash::Sha512(_) => Self::Sha512(Sha512::new()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha1(h) => h.update(bytes),
+            Self::Sha256(h) => h.update(bytes),
+            Self::Sha512(h) => h.update(bytes),
+        }
+    }
+
+    pub(crate) fn update_file(&mut self, path: &Path) -> std::io::Result<()> {
+        let mut file = std::fs::File::open(path)?;
+        let mut buf = vec![0; 64 * 1024];
+
+        loop {
+            let read = file.read(&mut buf)?;
+            if read == 0 {
+                return Ok(())
+            }
+            self.update(&buf[..read]);
+        }
+    }
+
+    pub(crate) fn finalize(self) -> String {
+        let digest = match self {
+            Self::Sha1(h) => h.finalize().to_vec(),
+            Self::Sha256(h) => h.finalize().to_vec(),
+            Self::Sha512(h) => h.finalize().to_vec(),
+        };
+
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
@@ -6,20 +6,41 @@ pub use r#async::*;
 mod error;
 pub use error::Error;
 
+mod hash;
+pub use hash::Hash;
+
+mod report;
+pub use report::{ Report, Failure };
+
+mod progress;
+pub use progress::Progress;
+
+mod policy;
+pub use policy::DownloadPolicy;
+
+#[cfg(test)]
+mod test;
+
 use std::path::{ Path, PathBuf };
 use std::fs::{ create_dir_all, File };
+use std::sync::mpsc::Sender;
 
 #[derive(Clone, Default, Debug)]
 pub struct Downloads {
     pub downloads: Vec<Download>,
-    pub retries: u8,
+    pub policy: DownloadPolicy,
+    pub progress: Option<Sender<Progress>>,
 }
 
 #[derive(Clone, Default, Debug)]
 pub struct Download {
     pub url: String,
+    /// Tried in order whenever the host behind `url` fails.
+    pub fallbacks: Vec<String>,
     pub path: PathBuf,
     pub unzip: bool,
+    pub hash: Option<Hash>,
+    pub size: Option<u64>,
 }
 
 pub fn create_file(path: &Path, read: bool, write: bool) -> std::io::Result<File> {
@@ -28,9 +49,11 @@ pub fn create_file(path: &Path, read: bool, write: bool) -> std::io::Result<File
         .write(write)
         .open(path);
 
-    if let Err(_) = file {
+    if file.is_err() {
         //handle non-existence of parent()
-        create_dir_all(&path.parent().unwrap())?;
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
 
         file = std::fs::OpenOptions::new()
             .read(read)
@@ -0,0 +1,111 @@
+use serde::{ Serialize, Deserialize };
+use std::collections::hash_map::RandomState;
+use std::hash::{ BuildHasher, Hasher };
+use std::sync::Mutex;
+use std::time::{ Duration, Instant, SystemTime };
+use crate::Error;
+
+/// How a [`crate::Downloads`] batch talks to the network.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadPolicy {
+    /// Requests in flight at once.
+    pub max_concurrent: usize,
+    /// Worker threads of the runtime driving the requests.
+    pub worker_threads: usize,
+    /// Cap on bytes received per second across all requests, unlimited when unset.
+    pub bytes_per_second: Option<u64>,
+    /// Attempts per item, including the first one.
+    pub retries: u8,
+    /// Delay before the first retry, doubled on every following one.
+    pub backoff_ms: u64,
+    /// Longest wait between attempts, however long a server's `Retry-After` asks for.
+    pub max_backoff_ms: u64,
+    /// Time allowed for a single request, body included.
+    pub timeout_secs: Option<u64>,
+    /// Response statuses worth another attempt; any other error status fails the item right away.
+    pub retry_statuses: Vec<u16>,
+}
+
+impl Default for DownloadPolicy {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 100,
+            worker_threads: 10,
+            bytes_per_second: None,
+            retries: 5,
+            backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            timeout_secs: Some(300),
+            retry_statuses: vec![408, 425, 429, 500, 502, 503, 504],
+        }
+    }
+}
+
+impl DownloadPolicy {
+    pub fn is_retryable(&self, err: &Error) -> bool {
+        match err {
+            Error::Status { status, .. } => self.retry_statuses.contains(status),
+            Error::IoError(_) => false,
+            _ => true,
+        }
+    }
+
+    /// Exponential backoff with jitter for the given failed attempt (starting at 1),
+    /// stretched to honour a server's `Retry-After` up to `max_backoff_ms`.
+    pub fn delay(&self, attempt: u8, err: &Error) -> Duration {
+        let exp = self.backoff_ms.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+        let capped = exp.min(self.max_backoff_ms);
+        let jittered = capped / 2 + random() % (capped / 2 + 1);
+        let delay = Duration::from_millis(jittered);
+
+        match err {
+            Error::Status { retry_after: Some(secs), .. } => {
+                delay.max(Duration::from_secs(*secs)).min(Duration::from_millis(self.max_backoff_ms))
+            },
+            _ => delay,
+        }
+    }
+}
+
+/// Seconds a `Retry-After` header asks to wait, given either as seconds or as an HTTP date.
+pub(crate) fn retry_after(value: &str) -> Option<u64> {
+    let value = value.trim();
+    value.parse().ok().or_else(|| {
+        let date = httpdate::parse_http_date(value).ok()?;
+        Some(date.duration_since(SystemTime::now()).map_or(0, |wait| wait.as_secs()))
+    })
+}
+
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// Shared pacing for `bytes_per_second`: every chunk books its share of time on one schedule.
+pub(crate) struct Throttle {
+    rate: u64,
+    next: Mutex<Instant>,
+}
+
+impl Throttle {
+    pub(crate) fn new(rate: u64) -> Self {
+        Self {
+            rate: rate.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub(crate) async fn consume(&self, bytes: u64) {
+        let wait = {
+            let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let start = (*next).max(now);
+            *next = start + Duration::from_secs_f64(bytes as f64 / self.rate as f64);
+            start.saturating_duration_since(now)
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+
+/// Events emitted while a [`crate::Downloads`] batch runs.
+#[derive(Debug, Clone)]
+pub enum Progress {
+    /// Sent once up front per batch; `bytes` only counts items with a known size.
+    Planned { files: usize, bytes: u64 },
+    Started { path: PathBuf, size: Option<u64> },
+    /// `offset` bytes were already on disk in a `.part` file.
+    Resumed { path: PathBuf, offset: u64 },
+    Received { path: PathBuf, bytes: u64 },
+    /// The previous attempt failed with `reason`; anything received for `path` so far is discarded.
+    Retry { path: PathBuf, attempt: u8, reason: String },
+    Skipped { path: PathBuf, size: Option<u64> },
+    Finished { path: PathBuf, success: bool },
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Events(pub(crate) Option<Sender<Progress>>);
+
+impl Events {
+    pub(crate) fn send(&self, event: impl FnOnce() -> Progress) {
+        if let Some(tx) = &self.0 {
+            // a receiver that went away just means nobody is watching anymore
+            let _ = tx.send(event());
+        }
+    }
+}
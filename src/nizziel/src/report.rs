@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+use crate::{ Download, Error };
+
+/// Outcome of a [`crate::download`] run, one entry per queued item.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub succeeded: Vec<PathBuf>,
+    pub failed: Vec<Failure>,
+    /// Items whose destination already held a file matching the expected hash.
+    pub skipped: Vec<PathBuf>,
+    /// Bytes received over the network, excluding any resumed from a `.part` file.
+    pub bytes: u64,
+}
+
+#[derive(Debug)]
+pub struct Failure {
+    pub download: Download,
+    pub reason: Error,
+}
+
+impl Report {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+pub(crate) enum Outcome {
+    Succeeded(u64),
+    Skipped,
+}
@@ -0,0 +1,224 @@
+use std::io::{ BufRead, BufReader, Write };
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::time::{ Duration, SystemTime };
+use crate::{ Download, Downloads, DownloadPolicy, Error, Hash, Progress, Report };
+
+/// sha1 of `hello` and `hello world`.
+const HELLO_SHA1: &str = "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d";
+const HELLO_WORLD_SHA1: &str = "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed";
+
+/// Answers every connection to a local port with `respond`, given the request head,
+/// for as long as the test runs.
+fn serve(respond: impl Fn(&str) -> Vec<u8> + Send + 'static) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().filter_map(Result::ok) {
+            let mut head = String::new();
+            let mut reader = BufReader::new(&stream);
+            while reader.read_line(&mut head).is_ok_and(|read| read > 2) {}
+            let _ = stream.write_all(&respond(&head));
+        }
+    });
+
+    url
+}
+
+fn response(status: &str, headers: &str, body: &[u8]) -> Vec<u8> {
+    let mut response = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n", status, body.len(), headers).into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+fn run(downloads: Vec<Download>, progress: Option<Sender<Progress>>) -> Report {
+    let dls = Downloads {
+        downloads,
+        policy: DownloadPolicy { retries: 2, backoff_ms: 1, max_backoff_ms: 1, ..Default::default() },
+        progress,
+    };
+
+    tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap()
+        .block_on(crate::download(dls))
+        .unwrap()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nizziel-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_hash_mismatch() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let url = serve(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        response("200 OK", "", b"hello world")
+    });
+
+    let dir = temp_dir("hash-mismatch");
+    let path = dir.join("file");
+    let report = run(vec![Download {
+        url: format!("{}/file", url),
+        path: path.clone(),
+        hash: Some(Hash::Sha1(HELLO_SHA1.to_string())),
+        ..Default::default()
+    }], None);
+
+    // retried once, then given up on without anything left in place
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    assert!(report.succeeded.is_empty());
+    assert!(matches!(&report.failed[..], [failure] if matches!(&failure.reason,
+        Error::HashMismatch { found, .. } if found == HELLO_WORLD_SHA1
+    )));
+    assert!(!path.exists());
+    assert!(!dir.join("file.part").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_size_mismatch() {
+    let url = serve(|_| response("200 OK", "", b"hello"));
+
+    let dir = temp_dir("size-mismatch");
+    let path = dir.join("file");
+    let report = run(vec![Download {
+        url: format!("{}/file", url),
+        path: path.clone(),
+        size: Some(10),
+        ..Default::default()
+    }], None);
+
+    assert!(matches!(&report.failed[..], [failure] if matches!(failure.reason, Error::SizeMismatch { expected: 10, found: 5, .. })));
+    assert!(!path.exists());
+    assert!(!dir.join("file.part").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_resume() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let url = serve(move |head| {
+        if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+            // promises the whole file but hangs up halfway
+            return b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\nConnection: close\r\n\r\nhello".to_vec()
+        }
+
+        match head.to_ascii_lowercase().contains("range: bytes=5-") {
+            true => response("206 Partial Content", "Content-Range: bytes 5-10/11\r\n", b" world"),
+            false => response("200 OK", "", b"hello world"),
+        }
+    });
+
+    let dir = temp_dir("resume");
+    let path = dir.join("file");
+    let (tx, rx) = std::sync::mpsc::channel();
+    let report = run(vec![Download {
+        url: format!("{}/file", url),
+        path: path.clone(),
+        hash: Some(Hash::Sha1(HELLO_WORLD_SHA1.to_string())),
+        size: Some(11),
+        ..Default::default()
+    }], Some(tx));
+
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    assert_eq!(report.succeeded, std::slice::from_ref(&path));
+    assert_eq!(report.bytes, 6);
+    assert!(rx.try_iter().any(|event| matches!(event, Progress::Resumed { offset: 5, .. })));
+    assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+    assert!(!dir.join("file.part").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_report() {
+    let url = serve(|head| match head.starts_with("GET /missing ") {
+        true => response("404 Not Found", "", b""),
+        false => response("200 OK", "", b"hello"),
+    });
+
+    let dir = temp_dir("report");
+    let (found, missing) = (dir.join("found"), dir.join("missing"));
+    let report = run(vec![
+        Download { url: format!("{}/missing", url), path: missing.clone(), ..Default::default() },
+        Download { url: format!("{}/found", url), path: found.clone(), ..Default::default() },
+    ], None);
+
+    assert!(!report.is_complete());
+    assert_eq!(report.succeeded, std::slice::from_ref(&found));
+    assert!(report.skipped.is_empty());
+    assert_eq!(report.bytes, 5);
+    assert!(matches!(&report.failed[..], [failure]
+        if failure.download.path == missing && matches!(failure.reason, Error::Status { status: 404, .. })
+    ));
+    assert!(!missing.exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_duplicates() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let url = serve(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        response("200 OK", "", b"hello")
+    });
+
+    // asset indexes list one object under several names
+    let dir = temp_dir("duplicates");
+    let path = dir.join("file");
+    let download = Download {
+        url: format!("{}/file", url),
+        path: path.clone(),
+        hash: Some(Hash::Sha1(HELLO_SHA1.to_string())),
+        size: Some(5),
+        ..Default::default()
+    };
+    let report = run(vec![download.clone(), download], None);
+
+    assert!(report.is_complete());
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+    assert_eq!(report.succeeded, std::slice::from_ref(&path));
+    assert_eq!(std::fs::read(&path).unwrap(), b"hello");
+    assert!(!dir.join("file.part").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_retry_delay() {
+    use crate::policy::retry_after;
+
+    let policy = DownloadPolicy { backoff_ms: 500, max_backoff_ms: 30_000, ..Default::default() };
+    let status = |retry_after| Error::Status { url: String::new(), status: 503, retry_after };
+
+    // doubled per attempt and jittered down to half at most, never past the cap
+    for _ in 0..16 {
+        assert!((250..=500).contains(&policy.delay(1, &status(None)).as_millis()));
+        assert!((1_000..=2_000).contains(&policy.delay(3, &status(None)).as_millis()));
+        assert!((15_000..=30_000).contains(&policy.delay(10, &status(None)).as_millis()));
+        assert!((15_000..=30_000).contains(&policy.delay(255, &status(None)).as_millis()));
+    }
+
+    // a server asking for longer is honoured up to the cap
+    assert_eq!(policy.delay(1, &status(Some(10))), Duration::from_secs(10));
+    assert_eq!(policy.delay(1, &status(Some(3600))), Duration::from_millis(30_000));
+
+    assert_eq!(retry_after(" 120 "), Some(120));
+    let in_a_minute = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(61));
+    assert!(retry_after(&in_a_minute).is_some_and(|secs| (59..=61).contains(&secs)));
+    assert_eq!(retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(0));
+    assert_eq!(retry_after("soon"), None);
+}
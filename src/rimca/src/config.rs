@@ -4,13 +4,16 @@ use std::path::PathBuf;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
-	pub base_dir: PathBuf
+	pub base_dir: PathBuf,
+	#[serde(default)]
+	pub settings: rimca::Settings,
 }
 
 impl Default for Config {
 	fn default() -> Self {
 		Config {
-			base_dir: BaseDirs::new().unwrap().home_dir().join(".minecraft")
+			base_dir: BaseDirs::new().unwrap().home_dir().join(".minecraft"),
+			settings: rimca::Settings::default(),
 		}
 	}
 }
\ No newline at end of file
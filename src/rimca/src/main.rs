@@ -2,6 +2,7 @@ mod config;
 use config::Config;
 
 mod logger;
+mod progress;
 mod test;
 
 use structopt::StructOpt;
@@ -20,17 +21,107 @@ pub fn main() {
 		Command::Delete{ instance } => rimca::delete(&instance, &cfg.base_dir).unwrap(),
         
         Command::Download(dl) => {
-			if let Some(_fabric) = dl.fabric {
-                rimca::download(&dl.instance, dl.version, Some(String::from("fabric")), &cfg.base_dir).unwrap()
-			} else {
-                rimca::download(&dl.instance, dl.version, Some(String::from("vanilla")), &cfg.base_dir).unwrap()
-			}
+            let scenario = dl.fabric.is_some().then(|| String::from("fabric"));
+            let (tx, rx) = std::sync::mpsc::channel();
+
+            let handle = std::thread::spawn(move || {
+                rimca::download(&dl.instance, dl.version, scenario, dl.assets.map(rimca::AssetFilter::from), &cfg.base_dir, &cfg.settings, Some(tx))
+            });
+
+            progress::render(rx);
+            handle.join().unwrap().unwrap()
+        },
+
+        Command::Verify(v) => {
+            let (tx, rx) = std::sync::mpsc::channel();
+
+            let handle = std::thread::spawn(move || {
+                rimca::verify(&v.instance, v.repair, &cfg.base_dir, &cfg.settings, Some(tx))
+                    .map(|report| (report, v.repair))
+            });
+
+            progress::render(rx);
+            let (report, repaired) = handle.join().unwrap().unwrap();
+
+            for path in &report.missing {
+                println!("missing  {}", path.display());
+            }
+            for path in &report.corrupt {
+                println!("corrupt  {}", path.display());
+            }
+            if let Some(vulnerability) = &report.log4j {
+                let mitigation = match vulnerability.mitigation {
+                    rimca::log4j::Mitigation::NoLookupsFlag => "disabling message lookups",
+                    rimca::log4j::Mitigation::PatchedConfig => "a patched logging config",
+                };
+                println!("log4j-core {} is vulnerable to Log4Shell, launches are mitigated with {}", vulnerability.version, mitigation);
+            }
+            println!("{} files checked, {} missing, {} corrupt", report.checked, report.missing.len(), report.corrupt.len());
+
+            if !report.is_intact() {
+                if repaired {
+                    println!("repaired");
+                } else {
+                    std::process::exit(1);
+                }
+            }
+        },
+
+        Command::Gc { dry_run } => {
+            let report = rimca::gc(&cfg.base_dir, dry_run, &cfg.settings).unwrap();
+            for path in &report.files {
+                println!("{}", path.display());
+            }
+
+            let verb = if dry_run { "would free" } else { "freed" };
+            println!("{} files, {} {} bytes", report.files.len(), verb, report.bytes);
         },
 
+        Command::Du => {
+            use indicatif::HumanBytes;
+            let usage = rimca::du(&cfg.base_dir, &cfg.settings).unwrap();
+
+            println!("{0: <20} {1: >12} {2: >12} {3: >12}", "instance", "own", "exclusive", "shared");
+            for instance in &usage.instances {
+                println!("{0: <20} {1: >12} {2: >12} {3: >12}", instance.name,
+                    HumanBytes(instance.own).to_string(), HumanBytes(instance.exclusive).to_string(), HumanBytes(instance.shared).to_string());
+            }
+
+            println!();
+            for (store, bytes) in &usage.stores {
+                println!("{0: <20} {1: >12}", store, HumanBytes(*bytes).to_string());
+            }
+        },
+
+        Command::Export(e) => {
+            let output = e.output.unwrap_or_else(|| std::path::PathBuf::from(format!("{}.zip", e.instance)));
+            rimca::export(&e.instance, e.bundle, &output, &cfg.base_dir, &cfg.settings).unwrap()
+        },
+
+        Command::Import { archive } => {
+            let instance = rimca::import(&archive, &cfg.base_dir, &cfg.settings).unwrap();
+            println!("{}", instance);
+        },
+
+        Command::Mirror(MirrorCommand::Serve { addr }) => rimca::serve(&cfg.base_dir, &addr).unwrap(),
+
         Command::Launch(l) => {
-        	rimca::launch(&l.instance, &l.username, l.game_output, &cfg.base_dir).unwrap()
+            let mode = if l.detach { rimca::LaunchMode::Detached } else { rimca::LaunchMode::Attached };
+
+            match rimca::launch(&l.instance, &l.username, l.game_output, mode, &cfg.base_dir, &cfg.settings).unwrap() {
+                rimca::Launched::Detached { pid, .. } => println!("{}", pid),
+                rimca::Launched::Exited { code: Some(0), .. } => {},
+                rimca::Launched::Exited { code, diagnosis, .. } => {
+                    if let Some(diagnosis) = diagnosis {
+                        print_diagnosis(&diagnosis);
+                    }
+                    std::process::exit(code.unwrap_or(1))
+                },
+            }
         },
 
+        Command::Diagnose { instance } => print_diagnosis(&rimca::diagnose(&instance, &cfg.base_dir).unwrap()),
+
         Command::List(list) => {
             if let Some(remote) = list.loader {
                 match remote {
@@ -49,7 +140,10 @@ pub fn main() {
                 }
             } else {
                 for instance in rimca::list_instances(&cfg.base_dir).unwrap() {
-                    println!("{}", instance.as_str());
+                    match rimca::running(&instance, &cfg.base_dir) {
+                        Some(pid) => println!("{0: <20} running ({1})", instance, pid),
+                        None => println!("{}", instance.as_str()),
+                    }
                 }    
             }
         }
@@ -59,6 +153,27 @@ pub fn main() {
 }
 
 
+fn print_diagnosis(diagnosis: &rimca::Diagnosis) {
+    if diagnosis.is_empty() {
+        println!("no crash reports found");
+        return;
+    }
+
+    for report in &diagnosis.reports {
+        println!("crash report  {}", report.path.display());
+        if let Some(headline) = &report.headline {
+            println!("  {}", headline);
+        }
+        if !report.suspected_mods.is_empty() {
+            println!("  suspected mods: {}", report.suspected_mods.join(", "));
+        }
+    }
+
+    for hint in &diagnosis.hints {
+        println!("hint: {}", hint);
+    }
+}
+
 #[derive(StructOpt)]
 pub struct Arguments {
     #[structopt(subcommand)]
@@ -84,9 +199,37 @@ pub enum Command {
     ///List installed minecraft instances
     List(List),
 
-    // #[structopt(no_version, global_settings = &[AppSettings::DisableVersion])]
-    // ///Verify integrity of game files of instance
-    // Verify { instance: String },
+    #[structopt(no_version, global_settings = &[AppSettings::DisableVersion])]
+    ///Explain the crash reports left by the last launch of an instance
+    Diagnose { instance: String },
+
+    #[structopt(no_version, global_settings = &[AppSettings::DisableVersion])]
+    ///Remove libraries, assets and meta no instance uses anymore
+    Gc {
+        #[structopt(short = "n", long)]
+        ///Only report what would be removed
+        dry_run: bool,
+    },
+
+    #[structopt(no_version, global_settings = &[AppSettings::DisableVersion])]
+    ///Show disk usage of instances and the shared libraries, assets and meta
+    Du,
+
+    #[structopt(no_version, global_settings = &[AppSettings::DisableVersion])]
+    ///Archive a minecraft instance
+    Export(Export),
+
+    #[structopt(no_version, global_settings = &[AppSettings::DisableVersion])]
+    ///Import an instance archived with export
+    Import { archive: std::path::PathBuf },
+
+    #[structopt(no_version, global_settings = &[AppSettings::DisableVersion])]
+    ///Share the local game files with other launchers on the network
+    Mirror(MirrorCommand),
+
+    #[structopt(no_version, global_settings = &[AppSettings::DisableVersion])]
+    ///Verify integrity of game files of instance
+    Verify(Verify),
 
     #[structopt(no_version, global_settings = &[AppSettings::DisableVersion])]
     ///Login a user
@@ -101,14 +244,17 @@ pub enum Command {
 #[derive(StructOpt)]
 pub struct Download {
     pub instance: String,
-    ///Vanilla version [default: latest_version]
+    ///Vanilla version [default: the instance's or latest_version]
     pub version: Option<String>,
     #[structopt(long, conflicts_with="fabric", value_name="version", require_equals=true)]
     ///Include forge [default version: latest_stable_version]
     pub forge: Option<Option<String>>,
     #[structopt(long, conflicts_with="forge", value_name="version", require_equals=true)]
-    ///Include fabric [default version: latest_stable_version]
-    pub fabric: Option<Option<String>>
+    ///Include fabric, kept for instances installed with it [default version: latest_stable_version]
+    pub fabric: Option<Option<String>>,
+    #[structopt(long, value_name="preset")]
+    ///Assets to download, `full` or `minimal` [default: the instance's or configured filter]
+    pub assets: Option<rimca::AssetPreset>,
 }
 
 #[derive(StructOpt)]
@@ -118,6 +264,38 @@ pub struct Launch {
     #[structopt(short = "q", long)]
     ///Print output of game to terminal
     pub game_output: bool,
+    #[structopt(short = "d", long)]
+    ///Leave the game running in the background instead of waiting for it
+    pub detach: bool,
+}
+
+#[derive(StructOpt)]
+pub struct Verify {
+    pub instance: String,
+    #[structopt(long)]
+    ///Download missing and corrupt files again
+    pub repair: bool,
+}
+
+#[derive(StructOpt)]
+pub struct Export {
+    pub instance: String,
+    #[structopt(long)]
+    ///Include the libraries, natives, assets and meta needed to launch offline
+    pub bundle: bool,
+    #[structopt(short, long)]
+    ///Archive to write [default: <instance>.zip]
+    pub output: Option<std::path::PathBuf>,
+}
+
+#[derive(StructOpt)]
+pub enum MirrorCommand {
+    ///Serve libraries, assets and meta over http for `lan` mirrors
+    Serve {
+        #[structopt(long, default_value = "0.0.0.0:8765")]
+        ///Address to listen on
+        addr: String,
+    },
 }
 
 #[derive(StructOpt)]
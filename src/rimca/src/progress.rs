@@ -0,0 +1,85 @@
+use indicatif::{ MultiProgress, ProgressBar, ProgressStyle };
+use std::collections::{ HashMap, HashSet };
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+use rimca::Progress;
+
+/// Renders download events until the sending side hangs up.
+pub fn render(rx: Receiver<Progress>) {
+    let multi = MultiProgress::new();
+
+    let files = multi.add(ProgressBar::new(0).with_style(
+        ProgressStyle::with_template("{prefix:>7} [{bar:40.cyan/blue}] {pos}/{len} files")
+            .unwrap()
+            .progress_chars("=> ")
+    ).with_prefix("files"));
+
+    let bytes = multi.add(ProgressBar::new(0).with_style(
+        ProgressStyle::with_template("{prefix:>7} [{bar:40.green/blue}] {bytes}/{total_bytes} {binary_bytes_per_sec} eta {eta}")
+            .unwrap()
+            .progress_chars("=> ")
+    ).with_prefix("bytes"));
+
+    let status = multi.add(ProgressBar::new_spinner().with_style(
+        ProgressStyle::with_template("{prefix:>7} {spinner} {wide_msg}").unwrap()
+    ).with_prefix("current"));
+    status.enable_steady_tick(Duration::from_millis(120));
+
+    // bytes per in-flight file, so a retry can take back what it had received
+    let mut received: HashMap<PathBuf, u64> = HashMap::new();
+    // files without a known size grow the byte total as they arrive
+    let mut unknown_size: HashSet<PathBuf> = HashSet::new();
+
+    for event in rx {
+        match event {
+            // a download can run in several batches, each planned as it starts
+            Progress::Planned { files: count, bytes: total } => {
+                files.inc_length(count as u64);
+                bytes.inc_length(total);
+            },
+
+            Progress::Started { path, size } => {
+                status.set_message(path.file_name().unwrap_or_default().to_string_lossy().to_string());
+                if size.is_none() {
+                    unknown_size.insert(path);
+                }
+            },
+
+            Progress::Resumed { path, offset: count } | Progress::Received { path, bytes: count } => {
+                if unknown_size.contains(&path) {
+                    bytes.inc_length(count);
+                }
+                bytes.inc(count);
+                *received.entry(path).or_default() += count;
+            },
+
+            Progress::Retry { path, attempt, reason } => {
+                let lost = received.remove(&path).unwrap_or(0);
+                if unknown_size.contains(&path) {
+                    bytes.set_length(bytes.length().unwrap_or(0).saturating_sub(lost));
+                }
+                bytes.set_position(bytes.position().saturating_sub(lost));
+                multi.suspend(|| log::warn!("Retrying {} (attempt {}): {}", path.display(), attempt + 1, reason));
+            },
+
+            Progress::Skipped { size, .. } => {
+                files.inc(1);
+                bytes.inc(size.unwrap_or(0));
+            },
+
+            Progress::Finished { path, success } => {
+                files.inc(1);
+                received.remove(&path);
+                unknown_size.remove(&path);
+                if !success {
+                    multi.suspend(|| log::error!("Failed to download {}", path.display()));
+                }
+            },
+        }
+    }
+
+    status.finish_and_clear();
+    files.finish();
+    bytes.finish();
+}
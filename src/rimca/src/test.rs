@@ -17,8 +17,9 @@ fn test_download() {
         version: Some(String::from("1.16.4")),
         forge: None,
         fabric: None,
+        assets: None,
     };
-    rimca::download(&dl.instance, dl.version, Some(String::from("vanilla")), &cfg.base_dir).unwrap()
+    rimca::download(&dl.instance, dl.version, Some(String::from("vanilla")), None, &cfg.base_dir, &cfg.settings, None).unwrap()
 }
 
 #[test]
@@ -30,6 +31,7 @@ fn test_launch() {
         instance: "test".to_string(),
         username: "Watson17".to_string(),
         game_output: true,
+        detach: false,
     };
-    rimca::launch(&launch.instance, &launch.username, launch.game_output, &cfg.base_dir).unwrap()
+    rimca::launch(&launch.instance, &launch.username, launch.game_output, rimca::LaunchMode::Attached, &cfg.base_dir, &cfg.settings).unwrap();
 }
\ No newline at end of file